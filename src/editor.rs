@@ -1,21 +1,61 @@
 mod annotatedstring;
 mod annotation;
 mod annotationtype;
+mod atomicwrite;
+mod bufferlist;
+mod clipboard;
 mod command;
+mod config;
 mod documentstatus;
+mod editorconfig;
+mod fileformat;
 mod fileinfo;
+mod filestamp;
+mod filetype;
+mod indentstyle;
 mod line;
+mod linenumbers;
+mod prompthistory;
+mod searchoptions;
+mod searchquery;
 mod terminal;
-mod filetype;
 mod uicomponents;
 use crate::prelude::*;
+use bufferlist::BufferList;
+use clipboard::Clipboard;
 use command::{Command, Edit, Move, System};
-use crossterm::event::{Event, KeyEvent, KeyEventKind, read};
+use config::Config;
+use crossterm::event::{
+    Event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind, poll, read,
+};
+use log::warn;
+use prompthistory::{HistoryKind, PromptHistory};
+use searchoptions::SearchOptions;
+use searchquery::SearchQuery;
+use std::{
+    cell::RefCell,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 use terminal::Terminal;
-use uicomponents::{CommandBar, MessageBar, StatusBar, UIComponent, View};
+use uicomponents::{
+    Buffer, CommandBar, MatchCount, MessageBar, Panes, SharedBuffer, SplitDirection, StatusBar,
+    Tab, TabBar, UIComponent,
+};
 
 const QUIT_TIMES: u8 = 3;
-const HELP_MESSAGE: &str = "HELP: Ctrl-F = Search | Ctrl-S = save | Ctrl-X = quit";
+/// The swap file is updated once no key was pressed for this long,
+const SWAP_IDLE_TIME: Duration = Duration::from_secs(2);
+/// or at least this often while typing
+const SWAP_INTERVAL: Duration = Duration::from_secs(10);
+/// The panes start below the tab bar
+const PANES_ORIGIN: Position = Position { row: 1, col: 0 };
+/// Alt with this number shows the last buffer
+const LAST_TAB_NUMBER: usize = 9;
+/// Lines a notch of the mouse wheel moves the caret by
+const WHEEL_LINES: usize = 3;
+const HELP_MESSAGE: &str = "HELP: Ctrl-F = find | Ctrl-R = replace | Ctrl-S = save | Ctrl-O = open | Ctrl-Z/Y = undo/redo | Ctrl-C/X/V = copy/cut/paste | Ctrl-Q = quit";
 
 type Result<T> = std::result::Result<T, std::io::Error>;
 
@@ -23,6 +63,20 @@ type Result<T> = std::result::Result<T, std::io::Error>;
 pub enum PromptType {
     Search,
     Save,
+    /// Asks for the file to open
+    Open,
+    /// Asks for the pattern to replace
+    Replace,
+    /// Asks for the text to replace the pattern with
+    ReplaceWith,
+    /// Asks what to do with each match
+    ReplaceConfirm,
+    /// Asks what to do with a swap file left behind by an earlier session
+    Recover,
+    /// Asks what to do about the file having been changed by another program
+    ExternalChange,
+    /// Asks whether to close a buffer with unsaved changes
+    CloseBuffer,
     #[default]
     None,
 }
@@ -31,12 +85,29 @@ impl PromptType {
     pub fn is_none(self) -> bool {
         self == Self::None
     }
+
+    /// Which history the values entered into this prompt are kept in
+    pub fn history_kind(self) -> Option<HistoryKind> {
+        match self {
+            Self::Search | Self::Replace => Some(HistoryKind::Search),
+            Self::ReplaceWith => Some(HistoryKind::Replacement),
+            Self::Save | Self::Open => Some(HistoryKind::FileName),
+            Self::ReplaceConfirm
+            | Self::Recover
+            | Self::ExternalChange
+            | Self::CloseBuffer
+            | Self::None => None,
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct Editor {
+    config: Config,
     should_quit: bool,
-    view: View,
+    panes: Panes,
+    buffers: BufferList,
+    tab_bar: TabBar,
     status_bar: StatusBar,
     message_bar: MessageBar,
     command_bar: CommandBar,
@@ -44,12 +115,22 @@ pub struct Editor {
     terminal_size: Size,
     quit_times: u8,
     prompt_type: PromptType,
+    clipboard: Clipboard,
+    pasted_last: bool,
+    search_options: SearchOptions,
+    prompt_history: PromptHistory,
+    /// Text of the swap file the user is asked to recover
+    recovered_text: Option<String>,
+    swap_updated_at: Option<Instant>,
+    /// Whether the user chose to keep editing after being told the file changed on disk,
+    /// so regaining focus doesn't ask again until the file is saved or reloaded
+    disk_change_dismissed: bool,
 }
 
 impl Editor {
     // --- Initialization ---
 
-    pub fn new(filename: Option<&String>) -> Result<Self> {
+    pub fn new(filenames: &[String]) -> Result<Self> {
         let current_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |panic_info| {
             let _ = Terminal::terminate();
@@ -60,18 +141,35 @@ impl Editor {
 
         let size = Terminal::size().unwrap_or_default();
         let mut editor = Self::default();
+        editor.config = Config::load();
+        editor.prompt_history = PromptHistory::load();
+        editor
+            .panes
+            .view_mut()
+            .set_line_numbers(editor.config.line_numbers);
+        editor
+            .panes
+            .view_mut()
+            .set_soft_wrap(editor.config.soft_wrap);
+        let initial_buffer = editor.panes.view().get_buffer();
+        initial_buffer
+            .borrow_mut()
+            .set_indent_style(editor.config.indent_style());
+        editor.buffers.push(initial_buffer);
+        if editor.config.mouse {
+            Terminal::enable_mouse_capture()?;
+        }
         editor.handle_resize_command(size);
         editor.message_bar.update_message(HELP_MESSAGE);
 
-        if let Some(file) = filename {
+        for file in filenames {
             debug_assert!(!file.is_empty());
-
-            if editor.view.load(file).is_err() {
-                editor
-                    .message_bar
-                    .update_message(&format!("ERR: Could not open file: {file}"));
-            }
+            editor.open_file(file);
         }
+        if let Some(first) = editor.buffers.get(0) {
+            editor.panes.view_mut().set_buffer(first);
+        }
+        editor.handle_buffer_shown();
 
         editor.refresh_status();
 
@@ -82,8 +180,8 @@ impl Editor {
 
     pub fn handle_resize_command(&mut self, to: Size) {
         self.terminal_size = to;
-        self.view.resize(Size {
-            height: to.height.saturating_sub(2),
+        self.panes.resize(Size {
+            height: to.height.saturating_sub(3),
             width: to.width,
         });
 
@@ -92,16 +190,29 @@ impl Editor {
             width: to.width,
         };
 
+        self.tab_bar.resize(bar_size);
         self.status_bar.resize(bar_size);
         self.message_bar.resize(bar_size);
         self.command_bar.resize(bar_size);
     }
 
     pub fn refresh_status(&mut self) {
-        let status = self.view.get_status();
+        let status = self.panes.view().get_status();
         let title = format!("{} - {NAME}", status.filename);
         self.status_bar.update_status(status);
 
+        let current = self.panes.view().get_buffer();
+        let tabs = self
+            .buffers
+            .iter()
+            .map(|buffer| Tab {
+                name: buffer.borrow().get_file_info().to_string(),
+                is_modified: buffer.borrow().is_dirty(),
+            })
+            .collect();
+        let current_idx = self.buffers.position(&current).unwrap_or_default();
+        self.tab_bar.update_tabs(tabs, current_idx);
+
         if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
             self.title = title;
         }
@@ -117,8 +228,17 @@ impl Editor {
                 break;
             }
 
-            match read() {
-                Ok(event) => self.evaluate_event(event),
+            match poll(SWAP_IDLE_TIME).and_then(|is_ready| is_ready.then(read).transpose()) {
+                Ok(Some(event)) => {
+                    self.evaluate_event(event);
+                    if self
+                        .swap_updated_at
+                        .is_none_or(|updated_at| updated_at.elapsed() >= SWAP_INTERVAL)
+                    {
+                        self.update_swap_file();
+                    }
+                }
+                Ok(None) => self.update_swap_file(),
                 Err(err) => {
                     #[cfg(debug_assertions)]
                     {
@@ -136,16 +256,27 @@ impl Editor {
     // --- Event Handling ---
 
     fn evaluate_event(&mut self, event: Event) {
+        if let Event::Paste(text) = event {
+            self.handle_paste_event(&text);
+            return;
+        }
+        if event == Event::FocusGained {
+            self.handle_focus_gained();
+            return;
+        }
+        if let Event::Mouse(mouse_event) = event {
+            self.handle_mouse_event(mouse_event);
+            return;
+        }
+
         let should_process = match &event {
             Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
             Event::Resize(_, _) => true,
             _ => false,
         };
 
-        if should_process {
-            if let Ok(command) = Command::try_from(event) {
-                self.process_command(command);
-            }
+        if should_process && let Ok(command) = Command::try_from(event) {
+            self.process_command(command);
         }
     }
 
@@ -159,8 +290,15 @@ impl Editor {
         }
 
         match self.prompt_type {
-            PromptType::Search => self.process_command_during_search(command),
-            PromptType::Save => self.process_command_during_save(command),
+            PromptType::Search | PromptType::Replace => {
+                self.process_command_during_search(command);
+            }
+            PromptType::Save | PromptType::Open => self.process_command_during_file_name(command),
+            PromptType::ReplaceWith => self.process_command_during_replace_with(command),
+            PromptType::ReplaceConfirm => self.process_command_during_replace_confirm(command),
+            PromptType::Recover => self.process_command_during_recover(command),
+            PromptType::ExternalChange => self.process_command_during_external_change(command),
+            PromptType::CloseBuffer => self.process_command_during_close_buffer(command),
             PromptType::None => self.process_command_no_prompt(command),
         }
     }
@@ -171,28 +309,131 @@ impl Editor {
             return;
         }
         self.reset_quit_times();
+        let pasted_last = std::mem::take(&mut self.pasted_last);
 
         match command {
             Command::System(System::Save) => self.handle_save_command(),
             Command::System(System::Search) => self.set_prompt(PromptType::Search),
-            Command::Edit(edit_command) => self.view.handle_edit_command(edit_command),
-            Command::Move(move_command) => self.view.handle_move_command(move_command),
-            Command::System(System::Quit | System::Resize(_) | System::Dismiss) => {}
+            Command::System(System::Replace) => self.set_prompt(PromptType::Replace),
+            Command::Edit(Edit::Copy) => self.handle_copy_command(),
+            Command::Edit(Edit::Cut) => self.handle_cut_command(),
+            Command::Edit(Edit::Paste) => self.handle_paste_command(false),
+            Command::Edit(Edit::PasteOlder) => self.handle_paste_command(pasted_last),
+            Command::Edit(edit_command) => self.panes.view_mut().handle_edit_command(edit_command),
+            Command::Move(move_command) => self.panes.view_mut().handle_move_command(move_command),
+            Command::Select(move_command) => {
+                self.panes.view_mut().handle_select_command(move_command);
+            }
+            Command::System(System::Dismiss) => self.panes.view_mut().clear_selection(),
+            Command::System(System::ConvertLineEndings) => {
+                let line_ending = self.panes.view_mut().toggle_line_ending();
+                self.message_bar
+                    .update_message(&format!("Line endings will be saved as {line_ending}."));
+            }
+            Command::System(System::ToggleLineNumbers) => self.handle_toggle_line_numbers_command(),
+            Command::System(System::ToggleSoftWrap) => self.handle_toggle_soft_wrap_command(),
+            Command::System(System::Reflow) => {
+                if !self.panes.view_mut().reflow(self.config.reflow_width) {
+                    self.message_bar.update_message("Nothing to reflow.");
+                }
+            }
+            Command::System(System::OpenFile) => self.set_prompt(PromptType::Open),
+            Command::System(direction @ (System::NextBuffer | System::PreviousBuffer)) => {
+                self.handle_switch_buffer_command(direction);
+            }
+            Command::System(System::CloseBuffer) => self.handle_close_buffer_command(),
+            Command::System(System::ListBuffers) => self.handle_list_buffers_command(),
+            Command::System(System::GoToBuffer(number)) => {
+                self.handle_go_to_buffer_command(number);
+            }
+            Command::System(System::SplitVertical) => {
+                self.handle_split_command(SplitDirection::Vertical);
+            }
+            Command::System(System::SplitHorizontal) => {
+                self.handle_split_command(SplitDirection::Horizontal);
+            }
+            Command::System(System::ClosePane) => self.handle_close_pane_command(),
+            Command::System(System::FocusPane(direction)) => {
+                if self.panes.focus_towards(direction) {
+                    self.handle_buffer_shown();
+                }
+            }
+            Command::System(System::GrowPane) => {
+                self.panes.resize_focused(true);
+            }
+            Command::System(System::ShrinkPane) => {
+                self.panes.resize_focused(false);
+            }
+            Command::System(
+                System::Quit
+                | System::Resize(_)
+                | System::ToggleRegex
+                | System::ToggleCase
+                | System::ToggleWholeWord
+                | System::NextMatch
+                | System::PreviousMatch,
+            ) => {}
         }
     }
 
-    fn process_command_during_save(&mut self, command: Command) {
+    /// Handles the prompts asking for a file name, to save as or to open
+    fn process_command_during_file_name(&mut self, command: Command) {
         match command {
-            Command::System(System::Quit | System::Resize(_) | System::Search | System::Save)
-            | Command::Move(_) => {}
+            Command::Move(direction @ (Move::Up | Move::Down)) => {
+                self.recall_history(direction);
+            }
+            Command::System(
+                System::Quit
+                | System::Resize(_)
+                | System::Search
+                | System::Replace
+                | System::Save
+                | System::ToggleRegex
+                | System::ToggleCase
+                | System::ToggleWholeWord
+                | System::NextMatch
+                | System::PreviousMatch
+                | System::ConvertLineEndings
+                | System::OpenFile
+                | System::NextBuffer
+                | System::PreviousBuffer
+                | System::CloseBuffer
+                | System::ListBuffers
+                | System::GoToBuffer(_)
+                | System::ToggleLineNumbers
+                | System::ToggleSoftWrap
+                | System::Reflow
+                | System::SplitVertical
+                | System::SplitHorizontal
+                | System::ClosePane
+                | System::FocusPane(_)
+                | System::GrowPane
+                | System::ShrinkPane,
+            )
+            | Command::Select(_) => {}
+            Command::Move(command) => self.command_bar.handle_move_command(command),
             Command::System(System::Dismiss) => {
+                let message = if self.prompt_type == PromptType::Open {
+                    "Open aborted."
+                } else {
+                    "Save aborted."
+                };
                 self.set_prompt(PromptType::None);
-                self.message_bar.update_message("Save aborted.");
+                self.message_bar.update_message(message);
             }
             Command::Edit(Edit::InsertNewLine) => {
                 let file_name = self.command_bar.value();
-                self.save(Some(&file_name));
-                self.set_prompt(PromptType::None);
+                self.add_to_history(&file_name);
+                if self.prompt_type == PromptType::Open {
+                    self.set_prompt(PromptType::None);
+                    if !file_name.is_empty() {
+                        self.open_file(&file_name);
+                        self.handle_buffer_shown();
+                    }
+                } else {
+                    self.save(Some(&file_name));
+                    self.set_prompt(PromptType::None);
+                }
             }
             Command::Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
         }
@@ -202,43 +443,454 @@ impl Editor {
         match command {
             Command::System(System::Dismiss) => {
                 self.set_prompt(PromptType::None);
-                self.view.dismiss_search();
+                self.panes.view_mut().dismiss_search();
             }
             Command::Edit(Edit::InsertNewLine) => {
-                self.set_prompt(PromptType::None);
-                self.view.exit_search();
+                self.add_to_history(&self.command_bar.value());
+                if self.prompt_type == PromptType::Replace {
+                    self.set_prompt(PromptType::ReplaceWith);
+                } else {
+                    self.set_prompt(PromptType::None);
+                    self.panes.view_mut().exit_search();
+                }
             }
             Command::Edit(edit_command) => {
                 self.command_bar.handle_edit_command(edit_command);
-                self.view.search(&self.command_bar.value());
+                self.search();
             }
-            Command::Move(Move::Down | Move::Right) => {
-                self.view.search_next();
+            Command::System(
+                toggle @ (System::ToggleRegex | System::ToggleCase | System::ToggleWholeWord),
+            ) => self.handle_search_option_command(toggle),
+            Command::Move(direction @ (Move::Up | Move::Down)) => {
+                if self.recall_history(direction) {
+                    self.search();
+                }
             }
-            Command::Move(Move::Up | Move::Left) => {
-                self.view.search_prev();
+            Command::System(System::NextMatch) => {
+                let wrapped = self.panes.view_mut().search_next();
+                self.update_match_status(wrapped.then_some("Search wrapped around to the top."));
             }
-            Command::System(System::Quit | System::Resize(_) | System::Search | System::Save)
-            | Command::Move(_) => {}
+            Command::System(System::PreviousMatch) => {
+                let wrapped = self.panes.view_mut().search_prev();
+                self.update_match_status(wrapped.then_some("Search wrapped around to the bottom."));
+            }
+            Command::System(
+                System::Quit
+                | System::Resize(_)
+                | System::Search
+                | System::Replace
+                | System::Save
+                | System::ConvertLineEndings
+                | System::OpenFile
+                | System::NextBuffer
+                | System::PreviousBuffer
+                | System::CloseBuffer
+                | System::ListBuffers
+                | System::GoToBuffer(_)
+                | System::ToggleLineNumbers
+                | System::ToggleSoftWrap
+                | System::Reflow
+                | System::SplitVertical
+                | System::SplitHorizontal
+                | System::ClosePane
+                | System::FocusPane(_)
+                | System::GrowPane
+                | System::ShrinkPane,
+            )
+            | Command::Select(_) => {}
+            Command::Move(command) => self.command_bar.handle_move_command(command),
         }
     }
 
+    fn process_command_during_replace_with(&mut self, command: Command) {
+        match command {
+            Command::System(System::Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.panes.view_mut().dismiss_search();
+                self.message_bar.update_message("Replace aborted.");
+            }
+            Command::Edit(Edit::InsertNewLine) => {
+                let replacement = self.command_bar.value();
+                self.add_to_history(&replacement);
+                if self.panes.view_mut().start_replace(&replacement) {
+                    self.set_prompt(PromptType::ReplaceConfirm);
+                } else {
+                    self.finish_replace();
+                }
+            }
+            Command::Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            Command::Move(direction @ (Move::Up | Move::Down)) => {
+                self.recall_history(direction);
+            }
+            Command::System(
+                System::Quit
+                | System::Resize(_)
+                | System::Search
+                | System::Replace
+                | System::Save
+                | System::ToggleRegex
+                | System::ToggleCase
+                | System::ToggleWholeWord
+                | System::NextMatch
+                | System::PreviousMatch
+                | System::ConvertLineEndings
+                | System::OpenFile
+                | System::NextBuffer
+                | System::PreviousBuffer
+                | System::CloseBuffer
+                | System::ListBuffers
+                | System::GoToBuffer(_)
+                | System::ToggleLineNumbers
+                | System::ToggleSoftWrap
+                | System::Reflow
+                | System::SplitVertical
+                | System::SplitHorizontal
+                | System::ClosePane
+                | System::FocusPane(_)
+                | System::GrowPane
+                | System::ShrinkPane,
+            )
+            | Command::Select(_) => {}
+            Command::Move(command) => self.command_bar.handle_move_command(command),
+        }
+    }
+
+    fn process_command_during_replace_confirm(&mut self, command: Command) {
+        let has_next_match = match command {
+            Command::Edit(Edit::Insert('y')) => self.panes.view_mut().replace_match(),
+            Command::Edit(Edit::Insert('n')) => self.panes.view_mut().skip_match(),
+            Command::Edit(Edit::Insert('a')) => {
+                self.panes.view_mut().replace_all();
+                false
+            }
+            Command::Edit(Edit::Insert('q')) | Command::System(System::Dismiss) => false,
+            _ => true,
+        };
+
+        if !has_next_match {
+            self.finish_replace();
+        }
+    }
+
+    fn process_command_during_recover(&mut self, command: Command) {
+        let Some(recovered_text) = &self.recovered_text else {
+            self.set_prompt(PromptType::None);
+            return;
+        };
+
+        match command {
+            Command::Edit(Edit::Insert('r')) => {
+                self.panes.view_mut().recover(recovered_text);
+                self.message_bar
+                    .update_message("Recovered unsaved changes. Save to keep them.");
+            }
+            Command::Edit(Edit::Insert('s')) => {
+                let difference = self.panes.view_mut().describe_recovery(recovered_text);
+                self.command_bar
+                    .set_prompt(&format!("{difference} (r)ecover, (d)iscard: "));
+                return;
+            }
+            Command::Edit(Edit::Insert('d')) => {
+                self.panes.view_mut().discard_swap_file();
+                self.message_bar.update_message("Discarded the swap file.");
+            }
+            Command::System(System::Dismiss) => {
+                self.message_bar.update_message(
+                    "Opened the file as it is on disk. The swap file is kept and not updated.",
+                );
+            }
+            _ => return,
+        }
+
+        self.recovered_text = None;
+        self.set_prompt(PromptType::None);
+    }
+
+    fn process_command_during_external_change(&mut self, command: Command) {
+        match command {
+            Command::Edit(Edit::Insert('r')) => {
+                self.set_prompt(PromptType::None);
+                self.reload();
+            }
+            Command::Edit(Edit::Insert('o')) => {
+                self.set_prompt(PromptType::None);
+                self.save(None);
+            }
+            Command::Edit(Edit::Insert('c')) | Command::System(System::Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.disk_change_dismissed = true;
+                self.panes.view_mut().take_changed_on_disk();
+                self.message_bar
+                    .update_message("Kept your version. The file on disk is unchanged.");
+            }
+            _ => {}
+        }
+    }
+
+    fn process_command_during_close_buffer(&mut self, command: Command) {
+        match command {
+            Command::Edit(Edit::Insert('y')) => {
+                self.set_prompt(PromptType::None);
+                self.close_buffer();
+            }
+            Command::Edit(Edit::Insert('n')) | Command::System(System::Dismiss) => {
+                self.set_prompt(PromptType::None);
+            }
+            _ => {}
+        }
+    }
+
+    fn finish_replace(&mut self) {
+        let count = self.panes.view_mut().finish_replace();
+        self.set_prompt(PromptType::None);
+        self.message_bar.update_message(&format!(
+            "Replaced {count} occurrence{}.",
+            if count == 1 { "" } else { "s" }
+        ));
+    }
+
+    // --- Clipboard ---
+
+    fn handle_copy_command(&mut self) {
+        if let Some(text) = self.panes.view_mut().selected_text() {
+            // Set first, so that a failure to reach the system clipboard replaces it
+            self.message_bar.update_message("Copied selection.");
+            self.copy(text);
+        }
+    }
+
+    fn handle_cut_command(&mut self) {
+        if let Some(text) = self.panes.view_mut().cut_selection() {
+            self.copy(text);
+        } else {
+            // Ctrl-X used to quit, before it became cut
+            self.message_bar
+                .update_message("Nothing to cut. Press Ctrl-Q to quit.");
+        }
+    }
+
+    fn copy(&mut self, text: String) {
+        if self.config.osc52_clipboard {
+            match Terminal::copy_to_clipboard(&text, self.config.osc52_max_bytes) {
+                Ok(true) => {}
+                Ok(false) => self
+                    .message_bar
+                    .update_message("Selection is too large for the system clipboard."),
+                Err(err) => self.message_bar.update_message(&format!(
+                    "ERR: Could not copy to the system clipboard: {err}"
+                )),
+            }
+        }
+
+        self.clipboard.copy(text);
+    }
+
+    /// Pastes the latest copied text. When `replace_last_paste` is set, the text pasted
+    /// right before is replaced by the next older entry of the clipboard instead.
+    fn handle_paste_command(&mut self, replace_last_paste: bool) {
+        if replace_last_paste {
+            if let Some(text) = self.clipboard.cycle() {
+                self.panes.view_mut().replace_last_paste(text);
+                self.pasted_last = true;
+            }
+        } else if let Some(text) = self.clipboard.current() {
+            self.panes.view_mut().paste(text);
+            self.pasted_last = true;
+        }
+    }
+
+    /// Inserts text pasted into the terminal verbatim, as a single edit. It bypasses
+    /// the per-key commands, so none of their behaviors get applied to the pasted text.
+    fn handle_paste_event(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        match self.prompt_type {
+            PromptType::None => {
+                self.reset_quit_times();
+                self.pasted_last = false;
+                self.panes.view_mut().paste(&text);
+            }
+            PromptType::Save | PromptType::Open | PromptType::ReplaceWith => {
+                self.command_bar.insert_str(&text);
+            }
+            PromptType::Search | PromptType::Replace => {
+                self.command_bar.insert_str(&text);
+                self.search();
+            }
+            PromptType::ReplaceConfirm
+            | PromptType::Recover
+            | PromptType::ExternalChange
+            | PromptType::CloseBuffer => {}
+        }
+    }
+
+    // --- Search ---
+
+    fn search(&mut self) {
+        match self
+            .panes
+            .view_mut()
+            .search(&self.command_bar.value(), self.search_options)
+        {
+            Ok(()) => self.update_match_status(None),
+            Err(err) => self
+                .command_bar
+                .set_status(&SearchQuery::describe_error(&err)),
+        }
+    }
+
+    /// Shows which match is selected out of how many, reporting a lack of matches
+    /// and the search wrapping around in the message bar as well
+    fn update_match_status(&mut self, wrap_message: Option<&str>) {
+        let status = match self.panes.view_mut().search_match_count() {
+            None => String::new(),
+            Some(MatchCount { total: 0, .. }) => {
+                self.message_bar.update_message("No matches.");
+                String::from("no matches")
+            }
+            Some(MatchCount {
+                current,
+                total,
+                is_capped,
+            }) => {
+                let current = current.map_or_else(|| String::from("?"), |idx| idx.to_string());
+                let total = if is_capped {
+                    format!("{total}+")
+                } else {
+                    total.to_string()
+                };
+                if let Some(message) = wrap_message {
+                    self.message_bar.update_message(message);
+                    format!("{current}/{total} (wrapped)")
+                } else {
+                    format!("{current}/{total}")
+                }
+            }
+        };
+        self.command_bar.set_status(&status);
+    }
+
+    fn handle_toggle_line_numbers_command(&mut self) {
+        self.config.line_numbers = self.config.line_numbers.next();
+        for view in self.panes.views_mut() {
+            view.set_line_numbers(self.config.line_numbers);
+        }
+        self.message_bar
+            .update_message(&format!("Line numbers: {}.", self.config.line_numbers));
+    }
+
+    fn handle_toggle_soft_wrap_command(&mut self) {
+        self.config.soft_wrap = !self.config.soft_wrap;
+        for view in self.panes.views_mut() {
+            view.set_soft_wrap(self.config.soft_wrap);
+        }
+        self.message_bar.update_message(if self.config.soft_wrap {
+            "Soft wrap on."
+        } else {
+            "Soft wrap off."
+        });
+    }
+
+    fn handle_search_option_command(&mut self, command: System) {
+        let options = &mut self.search_options;
+        match command {
+            System::ToggleRegex => options.regex = !options.regex,
+            System::ToggleCase => options.case = options.case.next(),
+            System::ToggleWholeWord => options.whole_word = !options.whole_word,
+            _ => return,
+        }
+
+        self.command_bar
+            .set_prompt(&self.search_prompt(self.prompt_type));
+        self.search();
+    }
+
+    fn search_prompt(&self, prompt_type: PromptType) -> String {
+        let action = if prompt_type == PromptType::Replace {
+            "Replace"
+        } else {
+            "Search"
+        };
+        format!(
+            "{action} [{}] (Ctrl-N/Ctrl-P to navigate): ",
+            self.search_options
+        )
+    }
+
+    // --- Swap File ---
+
+    fn update_swap_file(&mut self) {
+        if !self.config.swap_files {
+            return;
+        }
+
+        for buffer in self.buffers.iter() {
+            if let Err(err) = buffer.borrow_mut().update_swap_file() {
+                warn!("Could not write swap file: {err}");
+            }
+        }
+        self.swap_updated_at = Some(Instant::now());
+    }
+
     // --- Prompt Handling ---
 
     fn set_prompt(&mut self, prompt_type: PromptType) {
         match prompt_type {
             PromptType::None => self.message_bar.set_needs_redraw(true), //Ensures the message bar is properly painted during the next redraw cycle
             PromptType::Save => self.command_bar.set_prompt("Save as: "),
-            PromptType::Search => {
-                self.view.enter_search();
+            PromptType::Open => self.command_bar.set_prompt("Open: "),
+            PromptType::Search | PromptType::Replace => {
+                self.panes.view_mut().enter_search();
                 self.command_bar
-                    .set_prompt("Search (Esc to cancel, Arrows to navigate): ");
+                    .set_prompt(&self.search_prompt(prompt_type));
             }
+            PromptType::ReplaceWith => self.command_bar.set_prompt("Replace with: "),
+            PromptType::ReplaceConfirm => self
+                .command_bar
+                .set_prompt("Replace this match? (y)es, (n)o, (a)ll, (q)uit: "),
+            PromptType::Recover => self
+                .command_bar
+                .set_prompt("Unsaved changes found: (r)ecover, (s)how difference, (d)iscard: "),
+            PromptType::ExternalChange => self
+                .command_bar
+                .set_prompt("File changed on disk: (r)eload, (o)verwrite, (c)ancel: "),
+            PromptType::CloseBuffer => self
+                .command_bar
+                .set_prompt("Close without saving? (y)es, (n)o: "),
         }
         self.command_bar.clear_value();
+        self.command_bar.set_status("");
+        self.prompt_history.reset_recall();
         self.prompt_type = prompt_type;
     }
 
+    /// Replaces the prompt's value with the next older (`Move::Up`) or newer (`Move::Down`)
+    /// entry of its history. Returns whether the value was replaced.
+    fn recall_history(&mut self, direction: Move) -> bool {
+        let Some(kind) = self.prompt_type.history_kind() else {
+            return false;
+        };
+
+        let entry = match direction {
+            Move::Up => self.prompt_history.older(kind, &self.command_bar.value()),
+            Move::Down => self.prompt_history.newer(kind),
+            _ => None,
+        };
+
+        if let Some(entry) = entry {
+            self.command_bar.set_value(entry);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn add_to_history(&mut self, value: &str) {
+        if let Some(kind) = self.prompt_type.history_kind() {
+            self.prompt_history.add(kind, value);
+        }
+    }
+
     pub fn in_prompt(&self) -> bool {
         !self.prompt_type.is_none()
     }
@@ -246,23 +898,271 @@ impl Editor {
     // --- File Operations ---
 
     fn handle_save_command(&mut self) {
-        if self.view.is_file_loaded() {
-            self.save(None);
+        if self.panes.view_mut().is_file_loaded() {
+            if self.panes.view_mut().is_changed_on_disk() {
+                self.set_prompt(PromptType::ExternalChange);
+            } else {
+                self.save(None);
+            }
         } else {
             self.set_prompt(PromptType::Save);
         }
     }
 
     fn save(&mut self, file_name: Option<&str>) {
+        let keep_backup = self.config.backup_files;
         let result = if let Some(name) = file_name {
-            self.view.save_as(name)
+            self.panes.view_mut().save_as(name, keep_backup)
         } else {
-            self.view.save()
+            self.panes.view_mut().save(keep_backup)
         };
 
         match result {
-            Ok(()) => self.message_bar.update_message("File saved successfully."),
-            Err(_) => self.message_bar.update_message("Error writing file!"),
+            Ok(()) => {
+                self.disk_change_dismissed = false;
+                self.message_bar.update_message("File saved successfully.");
+            }
+            Err(err) => self
+                .message_bar
+                .update_message(&format!("Error writing file: {err}")),
+        }
+    }
+
+    fn reload(&mut self) {
+        match self.panes.view_mut().reload() {
+            Ok(()) => {
+                self.disk_change_dismissed = false;
+                self.message_bar
+                    .update_message("Reloaded the file from disk. Ctrl-Z restores your version.");
+            }
+            Err(err) => self
+                .message_bar
+                .update_message(&format!("Error reading file: {err}")),
+        }
+    }
+
+    /// Checks whether other programs changed the open files while the editor was in the
+    /// background. The changed ones are marked, and asked about once their buffer is shown.
+    fn handle_focus_gained(&mut self) {
+        let current = self.panes.view().get_buffer();
+        let mut others_changed: usize = 0;
+        for buffer in self.buffers.iter() {
+            if buffer.borrow_mut().check_changed_on_disk() && !Rc::ptr_eq(buffer, &current) {
+                others_changed = others_changed.saturating_add(1);
+            }
+        }
+
+        if !self.in_prompt()
+            && !self.disk_change_dismissed
+            && self.panes.view_mut().take_changed_on_disk()
+        {
+            self.set_prompt(PromptType::ExternalChange);
+        } else if others_changed > 0 {
+            self.message_bar.update_message(&format!(
+                "{others_changed} other open file(s) changed on disk. You will be asked once they are shown."
+            ));
+        }
+    }
+
+    /// Clicking a tab shows its buffer. With the mouse taken from the terminal,
+    /// its wheel moves the caret like the arrow keys, as terminals do otherwise.
+    fn handle_mouse_event(&mut self, event: MouseEvent) {
+        if self.in_prompt() {
+            return;
+        }
+
+        match event.kind {
+            // The tab bar is only there with room for it
+            MouseEventKind::Down(MouseButton::Left)
+                if event.row == 0 && self.terminal_size.height > 2 =>
+            {
+                if let Some(target) = self
+                    .tab_bar
+                    .tab_at(usize::from(event.column))
+                    .and_then(|idx| self.buffers.get(idx))
+                {
+                    self.show_buffer(target);
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                for _ in 0..WHEEL_LINES {
+                    self.process_command(Command::Move(Move::Up));
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                for _ in 0..WHEEL_LINES {
+                    self.process_command(Command::Move(Move::Down));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // --- Buffer Handling ---
+
+    /// Opens a file in a new buffer shown in the focused pane, or shows its buffer
+    /// if it is open already. Call `handle_buffer_shown` afterwards.
+    fn open_file(&mut self, file_name: &str) {
+        if let Some(buffer) = self.buffers.find_file(Path::new(file_name)) {
+            self.panes.view_mut().set_buffer(buffer);
+            return;
+        }
+
+        let Ok(buffer) = Buffer::load(file_name) else {
+            self.message_bar
+                .update_message(&format!("ERR: Could not open file: {file_name}"));
+            return;
+        };
+        let buffer = self.share_buffer(buffer);
+
+        // The empty buffer there is when starting without a file gets replaced
+        let current = self.panes.view().get_buffer();
+        if current.borrow().is_untouched() {
+            self.buffers.replace(&current, Rc::clone(&buffer));
+            self.panes.replace_buffer(&current, &buffer);
+        } else {
+            self.buffers.insert_after(&current, Rc::clone(&buffer));
+            self.panes.view_mut().set_buffer(buffer);
+        }
+    }
+
+    /// Makes a new buffer ready to be shown, with the settings from the config
+    fn share_buffer(&self, mut buffer: Buffer) -> SharedBuffer {
+        let indent_style = buffer.detect_indent_style(self.config.indent_style());
+        buffer.set_indent_style(indent_style);
+        Rc::new(RefCell::new(buffer))
+    }
+
+    /// Offers to recover the unsaved changes of a buffer which was just shown,
+    /// the first time it is shown, or else asks about changes to its file on disk
+    fn handle_buffer_shown(&mut self) {
+        self.disk_change_dismissed = false;
+
+        if self.config.swap_files && !self.in_prompt() {
+            self.recovered_text = self.panes.view_mut().take_recoverable_text();
+            if self.recovered_text.is_some() {
+                self.set_prompt(PromptType::Recover);
+            }
+        }
+
+        if !self.in_prompt() && self.panes.view_mut().take_changed_on_disk() {
+            self.set_prompt(PromptType::ExternalChange);
+        }
+    }
+
+    fn handle_switch_buffer_command(&mut self, direction: System) {
+        let current = self.panes.view().get_buffer();
+        let target = if matches!(direction, System::PreviousBuffer) {
+            self.buffers.previous(&current)
+        } else {
+            self.buffers.next(&current)
+        };
+
+        match target.filter(|target| !Rc::ptr_eq(target, &current)) {
+            Some(target) => {
+                let position = self.buffers.position(&target).unwrap_or_default();
+                self.panes.view_mut().set_buffer(target);
+                self.handle_buffer_shown();
+                self.message_bar.update_message(&format!(
+                    "Buffer {}/{}: {}",
+                    position.saturating_add(1),
+                    self.buffers.len(),
+                    self.panes.view().get_status().filename
+                ));
+            }
+            None => self
+                .message_bar
+                .update_message("No other buffers are open."),
+        }
+    }
+
+    fn handle_go_to_buffer_command(&mut self, number: usize) {
+        // Like in browsers, the last number goes to the last tab however many there are
+        let idx = if number == LAST_TAB_NUMBER {
+            self.buffers.len().saturating_sub(1)
+        } else {
+            number.saturating_sub(1)
+        };
+        let Some(target) = self.buffers.get(idx) else {
+            self.message_bar
+                .update_message(&format!("There is no buffer {number}."));
+            return;
+        };
+
+        self.show_buffer(target);
+    }
+
+    /// Shows a buffer in the focused pane, unless it is shown there already
+    fn show_buffer(&mut self, buffer: SharedBuffer) {
+        if !Rc::ptr_eq(&buffer, &self.panes.view().get_buffer()) {
+            self.panes.view_mut().set_buffer(buffer);
+            self.handle_buffer_shown();
+        }
+    }
+
+    fn handle_list_buffers_command(&mut self) {
+        let current = self.panes.view().get_buffer();
+        let list: Vec<String> = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(idx, buffer)| {
+                let modified = if buffer.borrow().is_dirty() { "+" } else { "" };
+                let entry = format!(
+                    "{}: {}{modified}",
+                    idx.saturating_add(1),
+                    buffer.borrow().get_file_info()
+                );
+                if Rc::ptr_eq(buffer, &current) {
+                    format!("[{entry}]")
+                } else {
+                    entry
+                }
+            })
+            .collect();
+
+        self.message_bar.update_message(&list.join(" | "));
+    }
+
+    fn handle_close_buffer_command(&mut self) {
+        if self.panes.view().get_status().is_modified {
+            self.set_prompt(PromptType::CloseBuffer);
+        } else {
+            self.close_buffer();
+        }
+    }
+
+    /// Closes the focused pane's buffer. Every pane showing it shows the next one instead.
+    fn close_buffer(&mut self) {
+        let closed = self.panes.view().get_buffer();
+        let replacement = self.buffers.remove(&closed).unwrap_or_else(|| {
+            let buffer = self.share_buffer(Buffer::default());
+            self.buffers.push(Rc::clone(&buffer));
+            buffer
+        });
+
+        self.panes.replace_buffer(&closed, &replacement);
+        closed.borrow_mut().remove_swap_file();
+        self.handle_buffer_shown();
+        self.message_bar
+            .update_message(&format!("Closed {}.", closed.borrow().get_file_info()));
+    }
+
+    // --- Pane Handling ---
+
+    fn handle_split_command(&mut self, direction: SplitDirection) {
+        if !self.panes.split(direction) {
+            self.message_bar
+                .update_message("Not enough room to split the pane.");
+        }
+    }
+
+    fn handle_close_pane_command(&mut self) {
+        if self.panes.close() {
+            self.disk_change_dismissed = false;
+        } else {
+            self.message_bar
+                .update_message("Can't close the only pane. Use Alt-K to close the buffer.");
         }
     }
 
@@ -271,11 +1171,22 @@ impl Editor {
     // clippy::arithmetic_side_effects: quit_times is guaranteed to be between 0 and QUIT_TIMES
     #[allow(clippy::arithmetic_side_effects)]
     fn handle_quit_command(&mut self) {
-        if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
+        let modified_count = self
+            .buffers
+            .iter()
+            .filter(|buffer| buffer.borrow().is_dirty())
+            .count();
+
+        if modified_count == 0 || self.quit_times + 1 == QUIT_TIMES {
             self.should_quit = true;
-        } else if self.view.get_status().is_modified {
+        } else {
+            let files = if modified_count == 1 {
+                String::from("File has")
+            } else {
+                format!("{modified_count} files have")
+            };
             self.message_bar.update_message(&format!(
-                "WARNING! File has unsaved changes. Press Ctrl-Q {} more times to quit.",
+                "WARNING! {files} unsaved changes. Press Ctrl-Q {} more times to quit.",
                 QUIT_TIMES - self.quit_times - 1
             ));
             self.quit_times += 1;
@@ -296,19 +1207,30 @@ impl Editor {
         let _ = Terminal::hide_caret();
         let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
 
+        let bottom_bar_origin = Position {
+            row: bottom_bar_row,
+            col: 0,
+        };
+
         if self.in_prompt() {
-            self.command_bar.render(bottom_bar_row);
+            self.command_bar.render(bottom_bar_origin);
         } else {
-            self.message_bar.render(bottom_bar_row);
+            self.message_bar.render(bottom_bar_origin);
         }
 
         if self.terminal_size.height > 1 {
-            self.status_bar
-                .render(self.terminal_size.height.saturating_sub(2));
+            self.status_bar.render(Position {
+                row: self.terminal_size.height.saturating_sub(2),
+                col: 0,
+            });
         }
 
         if self.terminal_size.height > 2 {
-            self.view.render(0);
+            self.tab_bar.render(Position::default());
+        }
+
+        if self.terminal_size.height > 3 {
+            self.panes.render(PANES_ORIGIN);
         }
 
         let new_caret_pos = if self.in_prompt() {
@@ -317,7 +1239,7 @@ impl Editor {
                 col: self.command_bar.caret_position_col(),
             }
         } else {
-            self.view.caret_position()
+            PANES_ORIGIN.saturating_add(self.panes.caret_position())
         };
 
         debug_assert!(new_caret_pos.col <= self.terminal_size.width);
@@ -331,6 +1253,15 @@ impl Editor {
 
 impl Drop for Editor {
     fn drop(&mut self) {
+        // Unwinding from a panic is the last chance to keep unsaved changes
+        if std::thread::panicking() {
+            self.update_swap_file();
+        } else {
+            for buffer in self.buffers.iter() {
+                buffer.borrow_mut().remove_swap_file();
+            }
+        }
+
         let _ = Terminal::terminate();
         if self.should_quit {
             let _ = Terminal::print("またね〜\r\n");
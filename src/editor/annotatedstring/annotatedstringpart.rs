@@ -1,7 +1,5 @@
 use crate::editor::annotationtype::AnnotationType;
 
-
-
 pub struct AnnotatedStringPart<'a> {
     pub string: &'a str,
     pub annotation_type: Option<AnnotationType>,
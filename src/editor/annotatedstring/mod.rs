@@ -19,8 +19,6 @@ pub struct AnnotatedString {
 
 impl AnnotatedString {
     pub fn from(string: &str) -> Self {
-        
-
         Self {
             string: String::from(string),
             annotations: Vec::new(),
@@ -2,6 +2,7 @@
 pub enum AnnotationType {
     Match,
     SelectedMatch,
+    Selection,
     Number,
     Keyword,
     Type,
@@ -10,4 +11,6 @@ pub enum AnnotationType {
     String,
     LifetimeSpecifier,
     Comment,
+    LineNumber,
+    CurrentLineNumber,
 }
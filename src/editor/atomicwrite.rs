@@ -0,0 +1,103 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions, Permissions},
+    io::{BufWriter, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// How many temporary file names are tried before giving up, should files of crashed
+/// processes with the same id be in the way
+const TEMP_FILE_ATTEMPTS: usize = 100;
+
+/// Writes a file without ever leaving it half written: the contents go to a temporary
+/// file next to it, which is synced to disk and then renamed over the original.
+/// The file gets the given permissions, or keeps the original's if there are none.
+/// With `keep_backup` its previous contents are kept in a `~` suffixed copy.
+pub fn write_atomically(
+    path: &Path,
+    keep_backup: bool,
+    permissions: Option<Permissions>,
+    write_contents: impl FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+) -> Result<(), Error> {
+    // Writing through a symlink replaces the file it points to, not the link
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let existing = fs::metadata(&path).ok();
+    let permissions = permissions.or_else(|| existing.as_ref().map(fs::Metadata::permissions));
+    let (file, temp_path) = create_temp_file(&path, permissions.as_ref())?;
+
+    let result = (|| {
+        // The mode it was created with is narrowed by the umask
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+
+        let mut writer = BufWriter::new(file);
+        write_contents(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        if keep_backup && existing.is_some() {
+            fs::copy(&path, with_file_name_suffix(&path, "~", false))?;
+        }
+        fs::rename(&temp_path, &path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Makes the rename itself durable. Not every platform allows syncing a directory.
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Creates a new temporary file next to a path, returning it along with its path.
+/// Where possible, it has the given permissions from the start, so that the contents
+/// are never readable by anyone who may not read the file.
+fn create_temp_file(
+    path: &Path,
+    permissions: Option<&Permissions>,
+) -> Result<(File, PathBuf), Error> {
+    let mut last_error = Error::from(ErrorKind::AlreadyExists);
+
+    for attempt in 0..TEMP_FILE_ATTEMPTS {
+        let suffix = if attempt == 0 {
+            format!(".{}.tmp", process::id())
+        } else {
+            format!(".{}.{attempt}.tmp", process::id())
+        };
+        let temp_path = with_file_name_suffix(path, &suffix, true);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if let Some(permissions) = permissions {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(permissions.mode());
+        }
+
+        match options.open(&temp_path) {
+            Ok(file) => return Ok((file, temp_path)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => last_error = err,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Appends a suffix to the file name of a path, optionally hiding the file by prefixing a dot
+fn with_file_name_suffix(path: &Path, suffix: &str, hidden: bool) -> PathBuf {
+    let mut file_name = OsString::new();
+    if hidden {
+        file_name.push(".");
+    }
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,93 @@
+use super::uicomponents::SharedBuffer;
+use std::{fs, path::Path, rc::Rc};
+
+/// The open buffers, in order. Panes can show any of them, several panes the same one.
+#[derive(Default)]
+pub struct BufferList {
+    buffers: Vec<SharedBuffer>,
+}
+
+impl BufferList {
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SharedBuffer> {
+        self.buffers.iter()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<SharedBuffer> {
+        self.buffers.get(idx).map(Rc::clone)
+    }
+
+    pub fn position(&self, buffer: &SharedBuffer) -> Option<usize> {
+        self.buffers
+            .iter()
+            .position(|other| Rc::ptr_eq(other, buffer))
+    }
+
+    /// Returns the buffer holding the given file, if it is open
+    pub fn find_file(&self, path: &Path) -> Option<SharedBuffer> {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        self.buffers
+            .iter()
+            .find(|buffer| {
+                buffer
+                    .borrow()
+                    .get_file_info()
+                    .get_path()
+                    .is_some_and(|buffer_path| {
+                        fs::canonicalize(buffer_path).unwrap_or_else(|_| buffer_path.to_path_buf())
+                            == path
+                    })
+            })
+            .map(Rc::clone)
+    }
+
+    pub fn push(&mut self, buffer: SharedBuffer) {
+        self.buffers.push(buffer);
+    }
+
+    /// Adds a buffer right after another one, or at the end if that one isn't in the list
+    pub fn insert_after(&mut self, after: &SharedBuffer, buffer: SharedBuffer) {
+        let idx = self
+            .position(after)
+            .map_or(self.buffers.len(), |idx| idx.saturating_add(1));
+        self.buffers.insert(idx, buffer);
+    }
+
+    /// Puts a buffer in the place of another one
+    pub fn replace(&mut self, old: &SharedBuffer, new: SharedBuffer) {
+        if let Some(slot) = self
+            .buffers
+            .iter_mut()
+            .find(|buffer| Rc::ptr_eq(buffer, old))
+        {
+            *slot = new;
+        }
+    }
+
+    /// Returns the buffer after the given one, wrapping around after the last one
+    pub fn next(&self, buffer: &SharedBuffer) -> Option<SharedBuffer> {
+        let next = self.position(buffer)?.saturating_add(1);
+        self.get(if next < self.len() { next } else { 0 })
+    }
+
+    /// Returns the buffer before the given one, wrapping around before the first one
+    pub fn previous(&self, buffer: &SharedBuffer) -> Option<SharedBuffer> {
+        let previous = self
+            .position(buffer)?
+            .checked_sub(1)
+            .unwrap_or_else(|| self.len().saturating_sub(1));
+        self.get(previous)
+    }
+
+    /// Removes a buffer. Returns the one taking its place: the buffer after it,
+    /// or the one before it if it was the last.
+    pub fn remove(&mut self, buffer: &SharedBuffer) -> Option<SharedBuffer> {
+        let idx = self.position(buffer)?;
+        self.buffers.remove(idx);
+        self.get(idx.min(self.len().saturating_sub(1)))
+    }
+}
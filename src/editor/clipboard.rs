@@ -0,0 +1,37 @@
+use std::collections::VecDeque;
+
+const KILL_RING_SIZE: usize = 16;
+
+/// Internal clipboard, remembering the most recent copies in a kill ring
+/// so that older ones can be pasted as well
+#[derive(Default)]
+pub struct Clipboard {
+    ring: VecDeque<String>,
+    position: usize,
+}
+
+impl Clipboard {
+    pub fn copy(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.ring.push_front(text);
+        self.ring.truncate(KILL_RING_SIZE);
+        self.position = 0;
+    }
+
+    /// Returns the entry which would be pasted next
+    pub fn current(&self) -> Option<&str> {
+        self.ring.get(self.position).map(String::as_str)
+    }
+
+    /// Moves on to the next older entry, wrapping around to the newest one, and returns it
+    pub fn cycle(&mut self) -> Option<&str> {
+        self.position = self
+            .position
+            .saturating_add(1)
+            .checked_rem(self.ring.len())?;
+        self.current()
+    }
+}
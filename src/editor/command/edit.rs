@@ -6,6 +6,13 @@ pub enum Edit {
     InsertNewLine,
     Delete,
     DeleteBackward,
+    DeleteWordBackward,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    PasteOlder,
 }
 
 impl TryFrom<KeyEvent> for Edit {
@@ -20,6 +27,17 @@ impl TryFrom<KeyEvent> for Edit {
             // Deletion
             (KeyCode::Backspace, _) => Ok(Self::DeleteBackward),
             (KeyCode::Delete, _) => Ok(Self::Delete),
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => Ok(Self::DeleteWordBackward),
+
+            // History
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => Ok(Self::Undo),
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => Ok(Self::Redo),
+
+            // Clipboard
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Ok(Self::Copy),
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => Ok(Self::Cut),
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => Ok(Self::Paste),
+            (KeyCode::Char('y'), KeyModifiers::ALT) => Ok(Self::PasteOlder),
 
             _ => Err(format!(
                 "Unsupported key code {:?} with modifiers {:?}",
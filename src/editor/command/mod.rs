@@ -10,6 +10,7 @@ pub use system::System;
 #[derive(Clone, Copy)]
 pub enum Command {
     Move(Move),
+    Select(Move),
     Edit(Edit),
     System(System),
 }
@@ -25,6 +26,7 @@ impl TryFrom<Event> for Command {
                 .or_else(|_| {
                     Move::try_from(key_event)
                         .map(Self::Move)
+                        .or_else(|_| Move::try_from_selecting(key_event).map(Self::Select))
                         .or_else(|_| System::try_from(key_event).map(Self::System))
                 })
                 .map_err(|_err| format!("Event not supported: {key_event:?}")),
@@ -41,3 +41,24 @@ impl TryFrom<KeyEvent> for Move {
         }
     }
 }
+
+impl Move {
+    /// Parses a movement made while holding Shift, which extends the selection
+    pub fn try_from_selecting(event: KeyEvent) -> Result<Self, String> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+
+        // Shifted characters are text input, not movement
+        if modifiers == KeyModifiers::SHIFT && !matches!(code, KeyCode::Char(_)) {
+            Self::try_from(KeyEvent {
+                modifiers: KeyModifiers::NONE,
+                ..event
+            })
+        } else {
+            Err(format!(
+                "Unsupported key code {code:?} or modifier {modifiers:?}"
+            ))
+        }
+    }
+}
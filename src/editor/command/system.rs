@@ -1,3 +1,4 @@
+use super::Move;
 use crate::prelude::Size;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
@@ -8,6 +9,34 @@ pub enum System {
     Quit,
     Dismiss,
     Search,
+    Replace,
+    ToggleRegex,
+    ToggleCase,
+    ToggleWholeWord,
+    NextMatch,
+    PreviousMatch,
+    ConvertLineEndings,
+    /// Switches the gutter between absolute, relative and no line numbers
+    ToggleLineNumbers,
+    /// Switches between wrapping long lines and scrolling horizontally
+    ToggleSoftWrap,
+    /// Re-wraps the selected lines, or the paragraph around the caret
+    Reflow,
+    OpenFile,
+    NextBuffer,
+    PreviousBuffer,
+    CloseBuffer,
+    ListBuffers,
+    /// Shows the buffer of the tab with this number, counting from 1
+    GoToBuffer(usize),
+    /// Splits the focused pane into two side by side
+    SplitVertical,
+    /// Splits the focused pane into two stacked ones
+    SplitHorizontal,
+    ClosePane,
+    FocusPane(Move),
+    GrowPane,
+    ShrinkPane,
 }
 
 impl TryFrom<KeyEvent> for System {
@@ -20,11 +49,47 @@ impl TryFrom<KeyEvent> for System {
 
         if modifiers == KeyModifiers::CONTROL {
             match code {
-                KeyCode::Char('x') => Ok(Self::Quit),
+                KeyCode::Char('q') => Ok(Self::Quit),
                 KeyCode::Char('s') => Ok(Self::Save),
                 KeyCode::Char('f') => Ok(Self::Search),
+                KeyCode::Char('r') => Ok(Self::Replace),
+                KeyCode::Char('n') => Ok(Self::NextMatch),
+                KeyCode::Char('p') => Ok(Self::PreviousMatch),
+                KeyCode::Char('o') => Ok(Self::OpenFile),
+                KeyCode::PageDown => Ok(Self::NextBuffer),
+                KeyCode::PageUp => Ok(Self::PreviousBuffer),
                 _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
             }
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                KeyCode::Char('r') => Ok(Self::ToggleRegex),
+                KeyCode::Char('c') => Ok(Self::ToggleCase),
+                KeyCode::Char('w') => Ok(Self::ToggleWholeWord),
+                KeyCode::Char('e') => Ok(Self::ConvertLineEndings),
+                KeyCode::Char('n') => Ok(Self::ToggleLineNumbers),
+                KeyCode::Char('z') => Ok(Self::ToggleSoftWrap),
+                KeyCode::Char('q') => Ok(Self::Reflow),
+                // For terminals which keep Ctrl-PageDown/PageUp to switch their own tabs
+                KeyCode::Char('.') => Ok(Self::NextBuffer),
+                KeyCode::Char(',') => Ok(Self::PreviousBuffer),
+                KeyCode::Char('k') => Ok(Self::CloseBuffer),
+                KeyCode::Char('b') => Ok(Self::ListBuffers),
+                KeyCode::Char(digit @ '1'..='9') => digit
+                    .to_digit(10)
+                    .and_then(|number| usize::try_from(number).ok())
+                    .map(Self::GoToBuffer)
+                    .ok_or_else(|| format!("Unsupported ALT+{code:?} combination")),
+                KeyCode::Char('v') => Ok(Self::SplitVertical),
+                KeyCode::Char('h') => Ok(Self::SplitHorizontal),
+                KeyCode::Char('x') => Ok(Self::ClosePane),
+                KeyCode::Left => Ok(Self::FocusPane(Move::Left)),
+                KeyCode::Right => Ok(Self::FocusPane(Move::Right)),
+                KeyCode::Up => Ok(Self::FocusPane(Move::Up)),
+                KeyCode::Down => Ok(Self::FocusPane(Move::Down)),
+                KeyCode::Char('=' | '+') => Ok(Self::GrowPane),
+                KeyCode::Char('-') => Ok(Self::ShrinkPane),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
         } else if modifiers == KeyModifiers::NONE && matches!(code, KeyCode::Esc) {
             Ok(Self::Dismiss)
         } else {
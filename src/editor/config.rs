@@ -0,0 +1,117 @@
+use super::indentstyle::{DEFAULT_TAB_WIDTH, IndentStyle};
+use super::linenumbers::LineNumbers;
+use log::warn;
+use std::{env, fs, path::PathBuf, str::FromStr};
+
+const CONFIG_FILE_NAME: &str = "config";
+
+/// User settings, read from `$XDG_CONFIG_HOME/hecto/config` (or `~/.config/hecto/config`).
+/// The file holds one `key = value` pair per line, lines starting with `#` are ignored.
+// clippy::struct_excessive_bools: each of them is a setting the user switches independently
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    /// Whether copied text is also sent to the terminal's clipboard through OSC 52
+    pub osc52_clipboard: bool,
+    /// Largest amount of text, in bytes, which is sent through OSC 52
+    pub osc52_max_bytes: usize,
+    /// Whether saving keeps the previous version of a file as `file~`
+    pub backup_files: bool,
+    /// Whether unsaved changes are kept in a swap file, to recover them after a crash
+    pub swap_files: bool,
+    /// Which line numbers the gutter shows: `off`, `absolute` or `relative`
+    pub line_numbers: LineNumbers,
+    /// Whether long lines continue on the next rows instead of scrolling horizontally
+    pub soft_wrap: bool,
+    /// Width, in columns, which reflowing a paragraph wraps it at
+    pub reflow_width: usize,
+    /// Columns between tab stops
+    pub tab_width: usize,
+    /// Whether indentation is made of spaces, which the Tab key inserts instead of a tab
+    pub expand_tabs: bool,
+    /// Columns per level of indentation made of spaces
+    pub indent_width: usize,
+    /// Whether the editor takes the mouse, to switch buffers by clicking their tabs.
+    /// Off by default, since the terminal then only selects and pastes text while Shift is held.
+    pub mouse: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            osc52_clipboard: true,
+            osc52_max_bytes: 100_000,
+            backup_files: false,
+            swap_files: true,
+            line_numbers: LineNumbers::default(),
+            soft_wrap: false,
+            reflow_width: 80,
+            tab_width: DEFAULT_TAB_WIDTH,
+            expand_tabs: false,
+            indent_width: DEFAULT_TAB_WIDTH,
+            mouse: false,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration file, falling back to the defaults for anything missing or invalid
+    pub fn load() -> Self {
+        let mut config = Self::default();
+
+        if let Some(contents) = Self::path().and_then(|path| fs::read_to_string(path).ok()) {
+            for line in contents.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                if let Some((key, value)) = line.split_once('=') {
+                    config.set(key.trim(), value.trim());
+                } else {
+                    warn!("Ignoring malformed config line: {line}");
+                }
+            }
+        }
+
+        config
+    }
+
+    pub const fn indent_style(&self) -> IndentStyle {
+        IndentStyle {
+            tab_width: self.tab_width,
+            expand_tabs: self.expand_tabs,
+            indent_width: self.indent_width,
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(CONFIG_FILE_NAME))
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "osc52_clipboard" => Self::parse_into(&mut self.osc52_clipboard, key, value),
+            "osc52_max_bytes" => Self::parse_into(&mut self.osc52_max_bytes, key, value),
+            "backup_files" => Self::parse_into(&mut self.backup_files, key, value),
+            "swap_files" => Self::parse_into(&mut self.swap_files, key, value),
+            "line_numbers" => Self::parse_into(&mut self.line_numbers, key, value),
+            "soft_wrap" => Self::parse_into(&mut self.soft_wrap, key, value),
+            "reflow_width" => Self::parse_into(&mut self.reflow_width, key, value),
+            "tab_width" => Self::parse_into(&mut self.tab_width, key, value),
+            "expand_tabs" => Self::parse_into(&mut self.expand_tabs, key, value),
+            "indent_width" => Self::parse_into(&mut self.indent_width, key, value),
+            "mouse" => Self::parse_into(&mut self.mouse, key, value),
+            _ => warn!("Ignoring unknown config key: {key}"),
+        }
+    }
+
+    fn parse_into<T: FromStr>(field: &mut T, key: &str, value: &str) {
+        match value.parse() {
+            Ok(parsed) => *field = parsed,
+            Err(_) => warn!("Ignoring invalid value for {key}: {value}"),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::filetype::FileType;
+use super::{fileformat::FileFormat, filetype::FileType};
 use crate::prelude::Location;
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -8,6 +8,7 @@ pub struct DocumentStatus {
     pub text_location: Location,
     pub is_modified: bool,
     pub file_type: FileType,
+    pub file_format: FileFormat,
 }
 
 impl DocumentStatus {
@@ -33,4 +34,18 @@ impl DocumentStatus {
     pub fn file_type_to_string(&self) -> String {
         self.file_type.to_string()
     }
+
+    pub fn file_format_to_string(&self) -> String {
+        let FileFormat {
+            line_ending,
+            encoding,
+            final_newline,
+        } = self.file_format;
+
+        if final_newline {
+            format!("{line_ending} {encoding}")
+        } else {
+            format!("{line_ending} {encoding} noeol")
+        }
+    }
 }
@@ -0,0 +1,453 @@
+use super::fileformat::{Encoding, FileFormat, LineEnding};
+use super::indentstyle::IndentStyle;
+use crate::prelude::*;
+use log::warn;
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{self, Path},
+};
+
+const EDITOR_CONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// Columns per level of indentation, as `.editorconfig` files give it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentSize {
+    Columns(ColIdx),
+    /// As wide as a tab
+    Tab,
+}
+
+/// Settings for a file from the `.editorconfig` files in its directory and the ones above it,
+/// see <https://editorconfig.org>. Settings none of them give are `None`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditorConfig {
+    expand_tabs: Option<bool>,
+    indent_size: Option<IndentSize>,
+    tab_width: Option<ColIdx>,
+    pub line_ending: Option<LineEnding>,
+    final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub encoding: Option<Encoding>,
+}
+
+impl EditorConfig {
+    /// Reads the settings for a file. The `.editorconfig` files are looked for from the file's
+    /// directory up to the one marked `root = true`, with the closer ones taking precedence,
+    /// as do later sections over earlier ones in the same file.
+    pub fn for_file(path: &Path) -> Self {
+        let mut result = Self::default();
+        let Ok(path) = path::absolute(path) else {
+            return result;
+        };
+
+        let mut files = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            let config_path = dir.join(EDITOR_CONFIG_FILE_NAME);
+            match fs::read_to_string(&config_path) {
+                Ok(contents) => {
+                    let file = EditorConfigFile::parse(&contents);
+                    let is_root = file.is_root;
+                    files.push((dir, file));
+                    if is_root {
+                        break;
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => warn!("Could not read {}: {err}", config_path.display()),
+            }
+        }
+
+        for (dir, file) in files.iter().rev() {
+            let Ok(relative_path) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let relative_path: Vec<char> = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+                .chars()
+                .collect();
+
+            for section in &file.sections {
+                if glob_matches(&section.glob, &relative_path) {
+                    for (key, value) in &section.properties {
+                        result.set(key, value);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "indent_style" => set_property(&mut self.expand_tabs, value, |value| match value {
+                "space" => Some(true),
+                "tab" => Some(false),
+                _ => None,
+            }),
+            "indent_size" => set_property(&mut self.indent_size, value, |value| match value {
+                "tab" => Some(IndentSize::Tab),
+                _ => parse_width(value).map(IndentSize::Columns),
+            }),
+            "tab_width" => set_property(&mut self.tab_width, value, parse_width),
+            "end_of_line" => set_property(&mut self.line_ending, value, |value| match value {
+                "lf" => Some(LineEnding::Lf),
+                "crlf" => Some(LineEnding::CrLf),
+                _ => None,
+            }),
+            "insert_final_newline" => set_property(&mut self.final_newline, value, parse_bool),
+            "trim_trailing_whitespace" => {
+                set_property(&mut self.trim_trailing_whitespace, value, parse_bool);
+            }
+            "charset" => set_property(&mut self.encoding, value, |value| match value {
+                "utf-8" => Some(Encoding::Utf8),
+                "utf-8-bom" => Some(Encoding::Utf8Bom),
+                "latin1" => Some(Encoding::Latin1),
+                _ => None,
+            }),
+            _ => {}
+        }
+    }
+
+    /// Returns the given indent style with the settings given here taking its place
+    pub fn indent_style(self, style: IndentStyle) -> IndentStyle {
+        let indent_columns = match self.indent_size {
+            Some(IndentSize::Columns(columns)) => Some(columns),
+            Some(IndentSize::Tab) | None => None,
+        };
+        // A tab is as wide as a level of indentation unless told otherwise
+        let tab_width = self.tab_width.or(indent_columns).unwrap_or(style.tab_width);
+
+        IndentStyle {
+            tab_width,
+            expand_tabs: self.expand_tabs.unwrap_or(style.expand_tabs),
+            indent_width: match self.indent_size {
+                Some(IndentSize::Columns(columns)) => columns,
+                Some(IndentSize::Tab) => tab_width,
+                None => style.indent_width,
+            },
+        }
+    }
+
+    /// Returns the given file format with the settings given here taking its place
+    pub fn file_format(self, format: FileFormat) -> FileFormat {
+        FileFormat {
+            line_ending: self.line_ending.unwrap_or(format.line_ending),
+            encoding: self.encoding.unwrap_or(format.encoding),
+            final_newline: self.final_newline.unwrap_or(format.final_newline),
+        }
+    }
+}
+
+/// Sets a property to its parsed value, or clears it if the value is `unset`.
+/// Values which can't be parsed leave it as it was.
+fn set_property<T>(property: &mut Option<T>, value: &str, parse: impl FnOnce(&str) -> Option<T>) {
+    if value == "unset" {
+        *property = None;
+    } else if let Some(value) = parse(value) {
+        *property = Some(value);
+    }
+}
+
+fn parse_width(value: &str) -> Option<ColIdx> {
+    value.parse().ok().filter(|width| *width > 0)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// The contents of a single `.editorconfig` file
+#[derive(Default)]
+struct EditorConfigFile {
+    /// Whether files in the directories above are not looked at
+    is_root: bool,
+    sections: Vec<Section>,
+}
+
+impl EditorConfigFile {
+    fn parse(contents: &str) -> Self {
+        let mut result = Self::default();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                // Globs without a slash match files in any directory below
+                let glob = match glob.strip_prefix('/') {
+                    Some(glob) => glob.to_string(),
+                    None if glob.contains('/') => glob.to_string(),
+                    None => format!("**/{glob}"),
+                };
+                result.sections.push(Section {
+                    glob: glob.chars().collect(),
+                    properties: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_ascii_lowercase();
+                let value = value.trim().to_ascii_lowercase();
+                match result.sections.last_mut() {
+                    Some(section) => section.properties.push((key, value)),
+                    // Only `root` belongs before the first section
+                    None if key == "root" => result.is_root = value == "true",
+                    None => {}
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Properties which apply to the files matching a glob
+struct Section {
+    /// Path relative to the directory of the `.editorconfig` file
+    glob: Vec<char>,
+    /// Keys and values, in lowercase
+    properties: Vec<(String, String)>,
+}
+
+/// Whether a path matches a glob, in which `*` matches anything but `/`, `**` matches
+/// anything, `?` matches any character but `/`, `[abc]`, `[a-z]` and `[!abc]` match
+/// characters in or not in a set, and `{a,b}` matches any of the alternatives
+fn glob_matches(glob: &[char], path: &[char]) -> bool {
+    if let Some(alternatives) = expand_braces(glob) {
+        return alternatives
+            .iter()
+            .any(|alternative| glob_matches(alternative, path));
+    }
+
+    let after = |skip: usize| path.get(skip..).unwrap_or_default();
+    match glob {
+        [] => path.is_empty(),
+        // Also matches no directories at all
+        ['*', '*', '/', rest @ ..] if glob_matches(rest, path) => true,
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|skip| glob_matches(rest, after(skip))),
+        ['*', rest @ ..] => {
+            let segment_len = path.iter().position(|ch| *ch == '/').unwrap_or(path.len());
+            (0..=segment_len).any(|skip| glob_matches(rest, after(skip)))
+        }
+        ['?', rest @ ..] => {
+            matches!(path, [ch, path_rest @ ..] if *ch != '/' && glob_matches(rest, path_rest))
+        }
+        ['[', class @ ..] if let Some(end) = class.iter().position(|ch| *ch == ']') => {
+            let rest = class.get(end.saturating_add(1)..).unwrap_or_default();
+            let class = class.get(..end).unwrap_or_default();
+            matches!(path, [ch, path_rest @ ..]
+                if *ch != '/' && class_contains(class, *ch) && glob_matches(rest, path_rest))
+        }
+        ['\\', escaped, rest @ ..] | [escaped, rest @ ..] => {
+            matches!(path, [ch, path_rest @ ..] if ch == escaped && glob_matches(rest, path_rest))
+        }
+    }
+}
+
+/// Whether a character is in the set inside the brackets of `[abc]`, `[a-z]` or `[!abc]`
+fn class_contains(class: &[char], ch: char) -> bool {
+    let (is_negated, mut class) = match class {
+        ['!', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut is_in_class = false;
+    while !class.is_empty() {
+        class = match class {
+            [from, '-', to, rest @ ..] => {
+                is_in_class = is_in_class || (*from..=*to).contains(&ch);
+                rest
+            }
+            [member, rest @ ..] => {
+                is_in_class = is_in_class || *member == ch;
+                rest
+            }
+            [] => class,
+        };
+    }
+
+    is_in_class != is_negated
+}
+
+/// Expands the first `{a,b}` in a glob into a glob for each alternative.
+/// Returns `None` if there are no braces with alternatives in it.
+fn expand_braces(glob: &[char]) -> Option<Vec<Vec<char>>> {
+    let mut start = None;
+    let mut depth: usize = 0;
+    let mut commas = Vec::new();
+    let mut is_escaped = false;
+
+    for (idx, ch) in glob.iter().enumerate() {
+        match ch {
+            _ if is_escaped => is_escaped = false,
+            '\\' => is_escaped = true,
+            '{' => {
+                start = start.or(Some(idx));
+                depth = depth.saturating_add(1);
+            }
+            ',' if depth == 1 => commas.push(idx),
+            '}' if depth > 0 => {
+                depth = depth.saturating_sub(1);
+                if depth > 0 {
+                    continue;
+                }
+                let start = start?;
+                if commas.is_empty() {
+                    // Braces around a single word are taken as they are
+                    return None;
+                }
+
+                let before = glob.get(..start).unwrap_or_default();
+                let after = glob.get(idx.saturating_add(1)..).unwrap_or_default();
+                let bounds = std::iter::once(start)
+                    .chain(commas.iter().copied())
+                    .zip(commas.iter().copied().chain(std::iter::once(idx)));
+                return Some(
+                    bounds
+                        .map(|(from, to)| {
+                            let alternative =
+                                glob.get(from.saturating_add(1)..to).unwrap_or_default();
+                            [before, alternative, after].concat()
+                        })
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EditorConfig, EditorConfigFile, IndentSize, glob_matches};
+    use crate::editor::fileformat::{Encoding, LineEnding};
+    use std::{fs, path::PathBuf};
+
+    fn matches(glob: &str, path: &str) -> bool {
+        let glob: Vec<char> = glob.chars().collect();
+        let path: Vec<char> = path.chars().collect();
+        glob_matches(&glob, &path)
+    }
+
+    /// Creates a directory of its own for a test, with the given files in it
+    fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hecto-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn double_star_slash_matches_no_directories() {
+        assert!(matches("**/*.rs", "main.rs"));
+        assert!(matches("**/*.rs", "src/editor/main.rs"));
+        assert!(matches("src/**/*.rs", "src/main.rs"));
+        assert!(!matches("src/**/*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+    }
+
+    #[test]
+    fn braces_match_any_alternative() {
+        assert!(matches("*.{rs,toml}", "main.rs"));
+        assert!(matches("*.{rs,toml}", "Cargo.toml"));
+        assert!(!matches("*.{rs,toml}", "README.md"));
+        assert!(matches("{src,tests}/*.{rs,txt}", "tests/notes.txt"));
+        // Braces around a single word are taken as they are
+        assert!(matches("{rs}", "{rs}"));
+    }
+
+    #[test]
+    fn negated_classes_match_characters_not_in_them() {
+        assert!(matches("[!a]b", "cb"));
+        assert!(!matches("[!a]b", "ab"));
+        assert!(matches("[!a-c]", "d"));
+        assert!(!matches("[!a-c]", "b"));
+        assert!(!matches("[!a]", "/"));
+        assert!(matches("[a-c]", "b"));
+    }
+
+    #[test]
+    fn parses_root_and_sections() {
+        let file = EditorConfigFile::parse(
+            "root = true\n# comment\n[*.rs]\nindent_style = Tab\n\n[/src/*]\ncharset = latin1\n",
+        );
+        assert!(file.is_root);
+        assert_eq!(file.sections.len(), 2);
+        assert_eq!(file.sections[0].glob, "**/*.rs".chars().collect::<Vec<_>>());
+        assert_eq!(
+            file.sections[0].properties,
+            vec![("indent_style".to_string(), "tab".to_string())]
+        );
+        assert_eq!(file.sections[1].glob, "src/*".chars().collect::<Vec<_>>());
+
+        assert!(!EditorConfigFile::parse("[*]\nroot = true\n").is_root);
+    }
+
+    #[test]
+    fn closer_files_take_precedence() {
+        let dir = test_dir(
+            "editorconfig-precedence",
+            &[
+                (
+                    ".editorconfig",
+                    "root = true\n[*]\nend_of_line = crlf\ntrim_trailing_whitespace = true\n",
+                ),
+                (
+                    "sub/.editorconfig",
+                    "[*.rs]\nend_of_line = lf\nindent_size = 2\n",
+                ),
+            ],
+        );
+
+        let config = EditorConfig::for_file(&dir.join("sub/main.rs"));
+        assert_eq!(config.line_ending, Some(LineEnding::Lf));
+        assert_eq!(config.trim_trailing_whitespace, Some(true));
+        assert_eq!(config.indent_size, Some(IndentSize::Columns(2)));
+
+        let config = EditorConfig::for_file(&dir.join("sub/notes.txt"));
+        assert_eq!(config.line_ending, Some(LineEnding::CrLf));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn root_files_hide_the_ones_above() {
+        let dir = test_dir(
+            "editorconfig-root",
+            &[
+                (".editorconfig", "root = true\n[*]\ncharset = latin1\n"),
+                (
+                    "sub/.editorconfig",
+                    "root = true\n[*]\ninsert_final_newline = false\n",
+                ),
+            ],
+        );
+
+        let config = EditorConfig::for_file(&dir.join("sub/main.rs"));
+        assert_eq!(config.encoding, None);
+        assert_eq!(config.final_newline, Some(false));
+
+        let config = EditorConfig::for_file(&dir.join("main.rs"));
+        assert_eq!(config.encoding, Some(Encoding::Latin1));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,512 @@
+use crate::prelude::*;
+use ropey::Rope;
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    io::{Error, ErrorKind, Read, Write},
+};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Characters which end a line in a file
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    /// Both, as read from a file. Which lines end with CRLF is kept apart from the text,
+    /// so that each is written back the way it was; new lines end with LF.
+    Mixed,
+}
+
+impl LineEnding {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf | Self::Mixed => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+
+    /// Returns the other line ending, for converting between them.
+    /// Mixed line endings are converted to LF.
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Lf => Self::CrLf,
+            Self::CrLf | Self::Mixed => Self::Lf,
+        }
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lf => write!(f, "LF"),
+            Self::CrLf => write!(f, "CRLF"),
+            Self::Mixed => write!(f, "Mixed"),
+        }
+    }
+}
+
+/// How the text of a file is encoded
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    /// Used for files which aren't valid UTF-8, as every byte maps to a character
+    Latin1,
+}
+
+impl Encoding {
+    /// Detects the encoding of a file's contents. Text which is valid UTF-8 is taken
+    /// to be Latin-1 anyway if the file is declared to be in it.
+    fn detect(bytes: &[u8], declared: Option<Self>) -> Self {
+        if declared == Some(Self::Latin1) {
+            return Self::Latin1;
+        }
+        match bytes.strip_prefix(UTF8_BOM) {
+            Some(rest) if str::from_utf8(rest).is_ok() => Self::Utf8Bom,
+            _ if str::from_utf8(bytes).is_ok() => Self::Utf8,
+            _ => Self::Latin1,
+        }
+    }
+
+    /// Decodes bytes which `detect` returned this encoding for
+    fn decode(self, mut bytes: Vec<u8>) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8(bytes).unwrap_or_default(),
+            Self::Utf8Bom => String::from_utf8(bytes.split_off(UTF8_BOM.len())).unwrap_or_default(),
+            Self::Latin1 => bytes.into_iter().map(char::from).collect(),
+        }
+    }
+
+    fn encode(self, text: &str) -> Result<Cow<'_, [u8]>, Error> {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => Ok(Cow::Borrowed(text.as_bytes())),
+            Self::Latin1 => text
+                .chars()
+                .map(|char| u8::try_from(u32::from(char)))
+                .collect::<Result<Vec<u8>, _>>()
+                .map(Cow::Owned)
+                .map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "Text contains characters which can't be encoded as Latin-1",
+                    )
+                }),
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "UTF-8"),
+            Self::Utf8Bom => write!(f, "UTF-8 BOM"),
+            Self::Latin1 => write!(f, "Latin-1"),
+        }
+    }
+}
+
+/// How a file's text is stored on disk, so that it can be written back the way it was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFormat {
+    pub line_ending: LineEnding,
+    pub encoding: Encoding,
+    /// Whether the last line ends with a line ending
+    pub final_newline: bool,
+}
+
+impl Default for FileFormat {
+    fn default() -> Self {
+        Self {
+            line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
+            final_newline: true,
+        }
+    }
+}
+
+impl FileFormat {
+    /// Detects the format of a file's contents. Returns it along with the decoded text,
+    /// in which lines are separated by `\n` and the final line ending is left out, and
+    /// the lines which end with CRLF if the file mixes line endings.
+    /// `declared_encoding` and `declared_line_ending` are the ones the file is meant to have,
+    /// if they are known. Files with a declared line ending are converted to it.
+    pub fn detect(
+        bytes: Vec<u8>,
+        declared_encoding: Option<Encoding>,
+        declared_line_ending: Option<LineEnding>,
+    ) -> (Self, String, Vec<LineIdx>) {
+        let encoding = Encoding::detect(&bytes, declared_encoding);
+        let mut line_endings = LineEndingStripper::default();
+        let mut stripped = Vec::with_capacity(bytes.len());
+        line_endings.strip(&bytes, &mut stripped);
+        line_endings.finish(&mut stripped);
+        drop(bytes);
+        let mut text = encoding.decode(stripped);
+
+        let (line_ending, crlf_lines) = line_endings.line_ending(declared_line_ending);
+
+        let final_newline = text.ends_with('\n');
+        if final_newline {
+            text.pop();
+        }
+
+        (
+            Self {
+                line_ending,
+                encoding,
+                final_newline,
+            },
+            text,
+            crlf_lines,
+        )
+    }
+
+    /// Reads UTF-8 text, with or without a byte order mark, straight into a rope, without
+    /// holding all of it in memory another time. Returns the same as `detect`.
+    /// Fails with `ErrorKind::InvalidData` if the text isn't UTF-8, which `detect` then
+    /// has to take as Latin-1.
+    pub fn read_utf8(
+        reader: impl Read,
+        declared_line_ending: Option<LineEnding>,
+    ) -> Result<(Self, Rope, Vec<LineIdx>), Error> {
+        let mut reader = LineEndingReader::new(reader);
+        let mut start = Vec::with_capacity(UTF8_BOM.len());
+        reader
+            .by_ref()
+            .take(u64::try_from(UTF8_BOM.len()).unwrap_or_default())
+            .read_to_end(&mut start)?;
+        let (encoding, start) = match start.strip_prefix(UTF8_BOM) {
+            Some(rest) => (Encoding::Utf8Bom, rest),
+            None => (Encoding::Utf8, start.as_slice()),
+        };
+        let mut text = Rope::from_reader(start.chain(reader.by_ref()))?;
+
+        let (line_ending, crlf_lines) = reader.line_endings.line_ending(declared_line_ending);
+
+        let len = text.len_chars();
+        let final_newline = len > 0 && text.char(len.saturating_sub(1)) == '\n';
+        if final_newline {
+            text.remove(len.saturating_sub(1)..);
+        }
+
+        Ok((
+            Self {
+                line_ending,
+                encoding,
+                final_newline,
+            },
+            text,
+            crlf_lines,
+        ))
+    }
+
+    /// Fails if the text can't be represented in this format's encoding.
+    /// Checking this before writing keeps a file from being left half written.
+    pub fn check_encodable<'a>(
+        self,
+        mut chunks: impl Iterator<Item = &'a str>,
+    ) -> Result<(), Error> {
+        chunks.try_for_each(|chunk| self.encoding.encode(chunk).map(|_| ()))
+    }
+
+    /// Writes text, given in chunks with lines separated by `\n`, in this format.
+    /// With mixed line endings, the lines in `crlf_lines`, which is sorted, end with CRLF.
+    pub fn write(
+        self,
+        writer: &mut impl Write,
+        chunks: impl Iterator<Item = impl AsRef<str>>,
+        crlf_lines: &[LineIdx],
+    ) -> Result<(), Error> {
+        if self.encoding == Encoding::Utf8Bom {
+            writer.write_all(UTF8_BOM)?;
+        }
+
+        let mut line_idx: LineIdx = 0;
+        let ending_of = |line_idx: LineIdx| match self.line_ending {
+            LineEnding::Mixed if crlf_lines.binary_search(&line_idx).is_ok() => "\r\n",
+            line_ending => line_ending.as_str(),
+        };
+        for chunk in chunks {
+            let chunk = chunk.as_ref();
+            let chunk = match self.line_ending {
+                LineEnding::Lf => Cow::Borrowed(chunk),
+                LineEnding::CrLf => Cow::Owned(chunk.replace('\n', "\r\n")),
+                LineEnding::Mixed => {
+                    let mut mixed = String::with_capacity(chunk.len());
+                    for piece in chunk.split_inclusive('\n') {
+                        match piece.strip_suffix('\n') {
+                            Some(line) => {
+                                mixed.push_str(line);
+                                mixed.push_str(ending_of(line_idx));
+                                line_idx = line_idx.saturating_add(1);
+                            }
+                            None => mixed.push_str(piece),
+                        }
+                    }
+                    Cow::Owned(mixed)
+                }
+            };
+            writer.write_all(&self.encoding.encode(&chunk)?)?;
+        }
+
+        // Also written for empty text, which is a single empty line then. Files which are
+        // empty on disk are detected without a final newline, and stay empty.
+        if self.final_newline {
+            writer.write_all(ending_of(line_idx).as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Size of the chunks `LineEndingReader` reads
+const READ_CHUNK_LEN: usize = 64 * 1024;
+
+/// Reader which turns CRLF line endings into LF as the text goes through it
+struct LineEndingReader<R: Read> {
+    inner: R,
+    line_endings: LineEndingStripper,
+    /// Chunk read last, as it came in
+    chunk: Vec<u8>,
+    /// That chunk with its line endings stripped
+    stripped: Vec<u8>,
+    /// How much of `stripped` was passed on already
+    passed_on: usize,
+}
+
+impl<R: Read> LineEndingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            line_endings: LineEndingStripper::default(),
+            chunk: vec![0; READ_CHUNK_LEN],
+            stripped: Vec::with_capacity(READ_CHUNK_LEN),
+            passed_on: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for LineEndingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        while self.passed_on >= self.stripped.len() {
+            self.stripped.clear();
+            self.passed_on = 0;
+            let read = self.inner.read(&mut self.chunk)?;
+            if read == 0 {
+                self.line_endings.finish(&mut self.stripped);
+                if self.stripped.is_empty() {
+                    return Ok(0);
+                }
+            } else {
+                let chunk = self.chunk.get(..read).unwrap_or_default();
+                self.line_endings.strip(chunk, &mut self.stripped);
+            }
+        }
+
+        let rest = self.stripped.get(self.passed_on..).unwrap_or_default();
+        let len = rest.len().min(buf.len());
+        buf.get_mut(..len)
+            .unwrap_or_default()
+            .copy_from_slice(rest.get(..len).unwrap_or_default());
+        self.passed_on = self.passed_on.saturating_add(len);
+        Ok(len)
+    }
+}
+
+/// Turns CRLF line endings into LF in text which is read in chunks,
+/// keeping track of which lines ended with which
+#[derive(Default)]
+struct LineEndingStripper {
+    /// Lines which ended with CRLF, in order
+    crlf_lines: Vec<LineIdx>,
+    /// Number of lines which ended with LF alone
+    lf_count: usize,
+    /// Whether the last chunk ended with `\r`, which is held back until the next one
+    /// shows whether `\n` follows
+    pending_cr: bool,
+}
+
+impl LineEndingStripper {
+    /// Appends a chunk to `stripped`, with its CRLF line endings turned into LF
+    fn strip(&mut self, chunk: &[u8], stripped: &mut Vec<u8>) {
+        let mut rest = chunk;
+        if self.pending_cr
+            && let Some((first, after_first)) = rest.split_first()
+        {
+            self.pending_cr = false;
+            if *first == b'\n' {
+                self.end_line(true, stripped);
+                rest = after_first;
+            } else {
+                stripped.push(b'\r');
+            }
+        }
+
+        while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+            let line = rest.get(..end).unwrap_or_default();
+            let without_cr = line.strip_suffix(b"\r");
+            stripped.extend_from_slice(without_cr.unwrap_or(line));
+            self.end_line(without_cr.is_some(), stripped);
+            rest = rest.get(end.saturating_add(1)..).unwrap_or_default();
+        }
+
+        match rest.strip_suffix(b"\r") {
+            Some(rest) => {
+                stripped.extend_from_slice(rest);
+                self.pending_cr = true;
+            }
+            None => stripped.extend_from_slice(rest),
+        }
+    }
+
+    /// Appends what was held back once all chunks went through
+    fn finish(&mut self, stripped: &mut Vec<u8>) {
+        if std::mem::take(&mut self.pending_cr) {
+            stripped.push(b'\r');
+        }
+    }
+
+    fn end_line(&mut self, is_crlf: bool, stripped: &mut Vec<u8>) {
+        if is_crlf {
+            self.crlf_lines
+                .push(self.crlf_lines.len().saturating_add(self.lf_count));
+        } else {
+            self.lf_count = self.lf_count.saturating_add(1);
+        }
+        stripped.push(b'\n');
+    }
+
+    /// Returns the line ending of the text, along with the lines ending with CRLF if it
+    /// mixes line endings. Text with a declared line ending was converted to it.
+    fn line_ending(self, declared: Option<LineEnding>) -> (LineEnding, Vec<LineIdx>) {
+        match (declared, self.crlf_lines.is_empty(), self.lf_count) {
+            (Some(declared), _, _) => (declared, Vec::new()),
+            (None, true, _) => (LineEnding::Lf, Vec::new()),
+            (None, false, 0) => (LineEnding::CrLf, Vec::new()),
+            // Files mixing both are written back unchanged, until they are converted
+            (None, false, _) => (LineEnding::Mixed, self.crlf_lines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoding, FileFormat, LineEnding};
+    use std::io::Read;
+
+    const FILES: [&[u8]; 9] = [
+        b"",
+        b"a\nb\n",
+        b"a\nb",
+        b"a\r\nb\r\n",
+        b"a\r\nb\nc\r\n\n",
+        b"a\rb\r",
+        b"\xEF\xBB\xBFa\r\n",
+        b"caf\xE9\n",
+        b"\xEF\xBB\xBF\xFF",
+    ];
+
+    fn round_trip(bytes: &[u8]) -> Vec<u8> {
+        let (format, text, crlf_lines) = FileFormat::detect(bytes.to_vec(), None, None);
+        let mut written = Vec::new();
+        format
+            .write(&mut written, std::iter::once(text), &crlf_lines)
+            .unwrap();
+        written
+    }
+
+    /// Reader handing out one byte at a time, to split line endings between reads
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(first)) => {
+                    *first = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn detects_formats() {
+        let detect = |bytes: &[u8]| FileFormat::detect(bytes.to_vec(), None, None);
+
+        let (format, text, _) = detect(b"a\r\nb\r\n");
+        assert_eq!(format.line_ending, LineEnding::CrLf);
+        assert!(format.final_newline);
+        assert_eq!(text, "a\nb");
+
+        let (format, text, crlf_lines) = detect(b"a\r\nb\nc\r\n\n");
+        assert_eq!(format.line_ending, LineEnding::Mixed);
+        assert_eq!(crlf_lines, vec![0, 2]);
+        assert_eq!(text, "a\nb\nc\n");
+
+        let (format, text, _) = detect(b"\xEF\xBB\xBFa");
+        assert_eq!(format.encoding, Encoding::Utf8Bom);
+        assert!(!format.final_newline);
+        assert_eq!(text, "a");
+
+        let (format, text, _) = detect(b"caf\xE9");
+        assert_eq!(format.encoding, Encoding::Latin1);
+        assert_eq!(text, "café");
+
+        let (format, _, _) = detect(b"");
+        assert!(!format.final_newline);
+    }
+
+    #[test]
+    fn writes_files_back_as_they_were() {
+        for bytes in FILES {
+            assert_eq!(round_trip(bytes), bytes, "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn converts_to_declared_formats() {
+        let (format, text, crlf_lines) = FileFormat::detect(
+            b"a\r\nb\n".to_vec(),
+            Some(Encoding::Latin1),
+            Some(LineEnding::Lf),
+        );
+        assert_eq!(format.line_ending, LineEnding::Lf);
+        assert_eq!(format.encoding, Encoding::Latin1);
+        assert!(crlf_lines.is_empty());
+        assert_eq!(text, "a\nb");
+    }
+
+    #[test]
+    fn reads_utf8_like_detect() {
+        for bytes in FILES {
+            let (format, text, crlf_lines) = FileFormat::detect(bytes.to_vec(), None, None);
+            match FileFormat::read_utf8(ByteReader(bytes), None) {
+                Ok((read_format, read_text, read_crlf_lines)) => {
+                    assert_eq!(read_format, format, "{bytes:?}");
+                    assert_eq!(read_text.to_string(), text, "{bytes:?}");
+                    assert_eq!(read_crlf_lines, crlf_lines, "{bytes:?}");
+                }
+                Err(err) => {
+                    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+                    assert_eq!(format.encoding, Encoding::Latin1, "{bytes:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_to_write_what_the_encoding_cannot_hold() {
+        let format = FileFormat {
+            encoding: Encoding::Latin1,
+            ..FileFormat::default()
+        };
+        assert!(format.check_encodable(["café"].into_iter()).is_ok());
+        assert!(format.check_encodable(["a", "€"].into_iter()).is_err());
+    }
+}
@@ -3,12 +3,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use super::filetype::FileType;
+use super::{fileformat::FileFormat, filestamp::FileStamp, filetype::FileType};
 
 #[derive(Default)]
 pub struct FileInfo {
     path: Option<PathBuf>,
     file_type: FileType,
+    format: FileFormat,
+    /// State of the file on disk when it was last loaded or saved
+    disk_stamp: Option<FileStamp>,
 }
 
 impl FileInfo {
@@ -26,9 +29,17 @@ impl FileInfo {
         Self {
             path: Some(path),
             file_type,
+            format: FileFormat::default(),
+            disk_stamp: None,
         }
     }
 
+    #[must_use]
+    pub const fn with_format(mut self, format: FileFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     pub fn get_path(&self) -> Option<&Path> {
         self.path.as_deref()
     }
@@ -40,6 +51,22 @@ impl FileInfo {
     pub const fn get_file_type(&self) -> FileType {
         self.file_type
     }
+
+    pub const fn get_format(&self) -> FileFormat {
+        self.format
+    }
+
+    pub const fn format_mut(&mut self) -> &mut FileFormat {
+        &mut self.format
+    }
+
+    pub const fn get_disk_stamp(&self) -> Option<FileStamp> {
+        self.disk_stamp
+    }
+
+    pub const fn set_disk_stamp(&mut self, disk_stamp: Option<FileStamp>) {
+        self.disk_stamp = disk_stamp;
+    }
 }
 
 impl Display for FileInfo {
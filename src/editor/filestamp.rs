@@ -0,0 +1,108 @@
+use std::{
+    fs::{self, Metadata},
+    hash::{DefaultHasher, Hasher},
+    io::{Error, Read, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+/// What a file on disk looked like when it was last loaded or saved,
+/// to notice when another program changes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub fn new(metadata: &Metadata, hash: u64) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash,
+        }
+    }
+
+    pub fn hash_bytes(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    /// Checks whether the file still has the contents it had. Its contents are only
+    /// hashed when the modification time changed but the size did not.
+    /// Returns the stamp of the file if it is unchanged, with an updated modification time.
+    pub fn check(&self, path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let stamp = Self::new(&metadata, self.hash);
+
+        if stamp.len != self.len {
+            return None;
+        }
+        if stamp.modified == self.modified {
+            return Some(stamp);
+        }
+
+        let hash = Self::hash_bytes(&fs::read(path).ok()?);
+        (hash == self.hash).then_some(stamp)
+    }
+}
+
+/// Writer which hashes everything written through it, the same way `FileStamp::hash_bytes` does
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: DefaultHasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let written = self.inner.write(buf)?;
+        self.hasher.write(buf.get(..written).unwrap_or_default());
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// Reader which hashes everything read through it, the same way `FileStamp::hash_bytes` does
+pub struct HashingReader<R: Read> {
+    inner: R,
+    hasher: DefaultHasher,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let read = self.inner.read(buf)?;
+        self.hasher.write(buf.get(..read).unwrap_or_default());
+        Ok(read)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+
+pub const DEFAULT_TAB_WIDTH: ColIdx = 4;
+/// Indentation of no more lines than this is looked at to detect how a file is indented
+const DETECTION_LINES: usize = 1000;
+
+/// How a buffer is indented
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IndentStyle {
+    /// Columns between tab stops
+    pub tab_width: ColIdx,
+    /// Whether indentation is made of spaces instead of tabs
+    pub expand_tabs: bool,
+    /// Columns per level of indentation made of spaces
+    pub indent_width: ColIdx,
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        Self {
+            tab_width: DEFAULT_TAB_WIDTH,
+            expand_tabs: false,
+            indent_width: DEFAULT_TAB_WIDTH,
+        }
+    }
+}
+
+impl IndentStyle {
+    /// One level of indentation
+    pub fn unit(self) -> String {
+        if self.expand_tabs {
+            " ".repeat(self.indent_width.max(1))
+        } else {
+            String::from("\t")
+        }
+    }
+
+    /// Returns this style adapted to how the given lines are indented already: with tabs
+    /// or spaces, whichever most lines use, and as many spaces as the most common step
+    /// from one line's indentation to a deeper one. Unindented text keeps this style.
+    pub fn detected_in<S: AsRef<str>>(self, lines: impl IntoIterator<Item = S>) -> Self {
+        let mut tab_lines: usize = 0;
+        let mut space_lines: usize = 0;
+        let mut steps: HashMap<ColIdx, usize> = HashMap::new();
+        let mut previous_spaces: ColIdx = 0;
+
+        for line in lines.into_iter().take(DETECTION_LINES) {
+            let line = line.as_ref();
+            let text = line.trim_start();
+            if text.is_empty() {
+                continue;
+            }
+
+            let spaces = line.len().saturating_sub(text.len());
+            if line.starts_with('\t') {
+                tab_lines = tab_lines.saturating_add(1);
+                continue;
+            }
+            // Continuation lines of block comments are off by one
+            if spaces > 0 && !text.starts_with('*') {
+                space_lines = space_lines.saturating_add(1);
+                if spaces > previous_spaces {
+                    let count = steps
+                        .entry(spaces.saturating_sub(previous_spaces))
+                        .or_default();
+                    *count = count.saturating_add(1);
+                }
+            }
+            previous_spaces = spaces;
+        }
+
+        if tab_lines > space_lines {
+            Self {
+                expand_tabs: false,
+                ..self
+            }
+        } else if let Some((indent_width, _)) = steps
+            .into_iter()
+            .max_by_key(|(step, count)| (*count, std::cmp::Reverse(*step)))
+        {
+            Self {
+                expand_tabs: true,
+                indent_width,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+}
@@ -1,6 +1,9 @@
 mod graphemewidth;
 mod textfragment;
-use super::{annotatedstring::AnnotatedString, annotation::Annotation};
+use super::{
+    annotatedstring::AnnotatedString, annotation::Annotation, indentstyle::DEFAULT_TAB_WIDTH,
+    searchquery::SearchQuery,
+};
 use crate::editor::line::graphemewidth::GraphemeWidth;
 use crate::prelude::*;
 use std::{
@@ -17,48 +20,66 @@ use unicode_width::UnicodeWidthStr;
 pub struct Line {
     fragments: Vec<TextFragment>,
     string: String,
+    /// Columns between tab stops
+    tab_width: ColIdx,
 }
 
 impl Line {
     pub fn from(line_str: &str) -> Self {
+        Self::with_tab_width(line_str, DEFAULT_TAB_WIDTH)
+    }
+
+    pub fn with_tab_width(line_str: &str, tab_width: ColIdx) -> Self {
         Self {
-            fragments: Self::str_to_fragments(line_str),
+            fragments: Self::str_to_fragments(line_str, tab_width),
             string: line_str.to_string(),
+            tab_width,
         }
     }
 
-    fn str_to_fragments(line_str: &str) -> Vec<TextFragment> {
+    fn str_to_fragments(line_str: &str, tab_width: ColIdx) -> Vec<TextFragment> {
+        let tab_width = tab_width.max(1);
+        let mut col: ColIdx = 0;
+
         line_str
             .grapheme_indices(true)
-            .map(|(idx, g)| TextFragment {
-                grapheme: g.to_string(),
-                rendered_width: match g.width() {
-                    0 | 1 => GraphemeWidth::Half,
+            .map(|(idx, g)| {
+                let rendered_width = match g {
+                    "\t" => GraphemeWidth::Tab(
+                        tab_width.saturating_sub(col.checked_rem(tab_width).unwrap_or(0)),
+                    ),
+                    _ if g.width() <= 1 => GraphemeWidth::Half,
                     _ => GraphemeWidth::Full,
-                },
-                replacement: Line::get_replacement_character(g),
-                start: idx,
+                };
+                col = col.saturating_add(rendered_width.into());
+
+                TextFragment {
+                    grapheme: g.to_string(),
+                    rendered_width,
+                    replacement: Line::get_replacement_character(g),
+                    start: idx,
+                }
             })
             .collect()
     }
 
     fn rebuild_fragments(&mut self) {
-        self.fragments = Self::str_to_fragments(&self.string);
+        self.fragments = Self::str_to_fragments(&self.string, self.tab_width);
     }
 
-    /// Replaces a grapheme with another character for display if needed
+    /// Replaces a grapheme with another character for display if needed.
+    /// Tabs are shown as spaces up to the next tab stop instead.
     fn get_replacement_character(g: &str) -> Option<char> {
         match g {
-            // TODO: Fix
-            "\t" => Some(' '),
-            " " => None,
+            "\t" | " " => None,
             _ if g.width() > 0 && g.trim().is_empty() => Some('␣'),
             _ if g.width() == 0 => {
                 let mut chars = g.chars();
-                if let Some(c) = chars.next() {
-                    if c.is_control() && chars.next().is_none() {
-                        return Some('▯');
-                    }
+                if let Some(c) = chars.next()
+                    && c.is_control()
+                    && chars.next().is_none()
+                {
+                    return Some('▯');
                 }
                 Some('·')
             }
@@ -88,46 +109,91 @@ impl Line {
             }
         }
 
+        // Going backwards keeps the byte positions of the fragments still to be visited
+        // valid while replacing graphemes and cutting off what lies outside the range.
+        // Graphemes only partially inside the range are shown as an ellipsis.
+        let mut fragment_start = self.width();
+        for fragment in self.fragments.iter().rev() {
+            let fragment_end = fragment_start;
+            fragment_start = fragment_start.saturating_sub(fragment.rendered_width.into());
+            let byte_end = fragment.start.saturating_add(fragment.grapheme.len());
+
+            if fragment_start >= range.end {
+                result.truncate_right_from(fragment.start);
+                continue;
+            }
+            let is_tab = matches!(fragment.rendered_width, GraphemeWidth::Tab(_));
+            if fragment_end > range.end {
+                let cut_off = if is_tab {
+                    " ".repeat(range.end.saturating_sub(fragment_start))
+                } else {
+                    String::from("⋯")
+                };
+                result.replace(fragment.start, self.string.len(), &cut_off);
+                continue;
+            }
+
+            if fragment_end <= range.start {
+                result.truncate_left_until(byte_end);
+                break;
+            }
+            if fragment_start < range.start {
+                let cut_off = if is_tab {
+                    " ".repeat(fragment_end.saturating_sub(range.start))
+                } else {
+                    String::from("⋯")
+                };
+                result.replace(0, byte_end, &cut_off);
+                break;
+            }
+
+            if is_tab {
+                let width = fragment_end.saturating_sub(fragment_start);
+                result.replace(fragment.start, byte_end, &" ".repeat(width));
+            } else if let Some(replacement) = fragment.replacement {
+                result.replace(fragment.start, byte_end, &replacement.to_string());
+            }
+        }
+
         result
     }
 
-    pub fn find_all(&self, query: &str, range: Range<ByteIdx>) -> Vec<(ByteIdx, GraphemeIdx)> {
+    /// Finds all matches of the query starting within the byte range,
+    /// returning their byte ranges and the index of the grapheme they start at
+    pub fn find_all(
+        &self,
+        query: &SearchQuery,
+        range: Range<ByteIdx>,
+    ) -> Vec<(Range<ByteIdx>, GraphemeIdx)> {
         let end = min(range.end, self.string.len());
         let start = range.start;
         debug_assert!(start <= end);
         debug_assert!(start <= self.string.len());
 
-        self.string.get(start..end).map_or_else(Vec::new, |substr| {
-            let potential_matches: Vec<ByteIdx> = substr
-                .match_indices(query)
-                .map(|(relative_start_idx, _)| relative_start_idx.saturating_add(start))
-                .collect();
-
-            self.match_grapheme_clusters(&potential_matches, query)
-        })
+        let potential_matches = query.find_all(&self.string, start..end);
+        self.match_grapheme_clusters(potential_matches)
     }
 
+    /// Keeps only the matches which start and end on grapheme boundaries
     fn match_grapheme_clusters(
         &self,
-        matches: &[ByteIdx],
-        query: &str,
-    ) -> Vec<(ByteIdx, GraphemeIdx)> {
-        let grapheme_count = query.graphemes(true).count();
+        matches: Vec<Range<ByteIdx>>,
+    ) -> Vec<(Range<ByteIdx>, GraphemeIdx)> {
         matches
-            .iter()
-            .filter_map(|&start| {
-                self.byte_idx_to_grapheme_idx(start)
-                    .and_then(|grapheme_idx| {
-                        self.fragments
-                            .get(grapheme_idx..grapheme_idx.saturating_add(grapheme_count))
-                            .and_then(|fragments| {
-                                let substring = fragments
-                                    .iter()
-                                    .map(|fragment| fragment.grapheme.as_str())
-                                    .collect::<String>();
-                                (query == substring).then_some((start, grapheme_idx))
-                            })
-                    })
+            .into_iter()
+            .filter_map(|range| {
+                let grapheme_idx = self.byte_idx_to_grapheme_idx(range.start)?;
+                let starts_on_boundary = self
+                    .fragments
+                    .get(grapheme_idx)
+                    .is_some_and(|fragment| fragment.start == range.start);
+                let ends_on_boundary = range.end == self.string.len()
+                    || self
+                        .fragments
+                        .iter()
+                        .any(|fragment| fragment.start == range.end);
+
+                (starts_on_boundary && ends_on_boundary).then_some((range, grapheme_idx))
             })
             .collect()
     }
@@ -142,25 +208,10 @@ impl Line {
         self.fragments
             .iter()
             .take(grapheme_idx)
-            .map(|fragment| match fragment.rendered_width {
-                GraphemeWidth::Half => 1,
-                GraphemeWidth::Full => 2,
-            })
+            .map(|fragment| usize::from(fragment.rendered_width))
             .sum()
     }
 
-    /// Inserts a character at a position in the line, or appends it if `at` == `grapheme_count + 1`
-    pub fn insert_char(&mut self, char: char, at: GraphemeIdx) {
-        debug_assert!(at.saturating_sub(1) <= self.grapheme_count());
-        if let Some(fragment) = self.fragments.get(at) {
-            self.string.insert(fragment.start, char);
-        } else {
-            self.string.push(char);
-        }
-
-        self.rebuild_fragments();
-    }
-
     /// Deletes a character at a position
     pub fn delete(&mut self, at: GraphemeIdx) {
         debug_assert!(at <= self.grapheme_count());
@@ -173,36 +224,126 @@ impl Line {
         }
     }
 
-    /// Appends a Line to self
-    pub fn append(&mut self, other: &Self) {
-        self.string.push_str(&other.string);
+    pub fn width(&self) -> ColIdx {
+        self.width_until(self.grapheme_count())
+    }
+
+    /// Inserts a string at a byte position, which must lie on a char boundary
+    pub fn insert_str_at_byte(&mut self, at: ByteIdx, string: &str) {
+        debug_assert!(self.string.is_char_boundary(at));
+        self.string.insert_str(at, string);
         self.rebuild_fragments();
     }
 
-    /// Splits Line into two
-    pub fn split(&mut self, at: GraphemeIdx) -> Self {
-        if let Some(fragment) = self.fragments.get(at) {
-            let remainder = self.string.split_off(fragment.start);
-            self.rebuild_fragments();
-            Self::from(&remainder)
-        } else {
-            Self::default()
+    /// Removes the given byte range from the line, returning the removed text
+    pub fn remove_bytes(&mut self, range: Range<ByteIdx>) -> String {
+        debug_assert!(range.start <= range.end && range.end <= self.string.len());
+        let removed = self.string.drain(range).collect();
+        self.rebuild_fragments();
+        removed
+    }
+
+    /// Returns the index of the grapheme starting the word before `at`,
+    /// skipping any whitespace right before it
+    pub fn word_start_before(&self, at: GraphemeIdx) -> GraphemeIdx {
+        let is_whitespace = |fragment: &TextFragment| fragment.grapheme.trim().is_empty();
+        let before = self.fragments.get(..at).unwrap_or(&self.fragments);
+
+        let word_end = before
+            .iter()
+            .rposition(|fragment| !is_whitespace(fragment))
+            .map_or(0, |idx| idx.saturating_add(1));
+        before
+            .get(..word_end)
+            .and_then(|word| word.iter().rposition(is_whitespace))
+            .map_or(0, |idx| idx.saturating_add(1))
+    }
+
+    /// Splits the line into rows no wider than `width` for soft wrapping, breaking after
+    /// whitespace where possible. Returns the index of the grapheme each row starts at.
+    /// A line filling its last row exactly gets an empty row after it, for the caret at its end.
+    pub fn wrap(&self, width: ColIdx) -> Vec<GraphemeIdx> {
+        let mut row_starts = vec![0];
+        if width == 0 {
+            return row_starts;
         }
+
+        let mut row_start: GraphemeIdx = 0;
+        let mut row_width: ColIdx = 0;
+        // Where the row can be broken without splitting a word
+        let mut break_at: GraphemeIdx = 0;
+
+        for (idx, fragment) in self.fragments.iter().enumerate() {
+            let fragment_width: ColIdx = fragment.rendered_width.into();
+            let is_whitespace = fragment.grapheme.trim().is_empty();
+
+            if row_width.saturating_add(fragment_width) > width {
+                // Whitespace hangs past the end of the row rather than starting the next one
+                if is_whitespace {
+                    row_start = idx.saturating_add(1);
+                    break_at = row_start;
+                    row_width = 0;
+                    row_starts.push(row_start);
+                    continue;
+                }
+
+                row_start = if break_at > row_start { break_at } else { idx };
+                row_width = self
+                    .width_until(idx)
+                    .saturating_sub(self.width_until(row_start));
+                row_starts.push(row_start);
+            }
+
+            row_width = row_width.saturating_add(fragment_width);
+            if is_whitespace {
+                break_at = idx.saturating_add(1);
+            }
+        }
+
+        if row_width == width {
+            row_starts.push(self.grapheme_count());
+        }
+
+        row_starts
     }
 
-    pub fn append_char(&mut self, char: char) {
-        self.insert_char(char, self.grapheme_count());
+    /// Returns the index of the grapheme covering a column, or the grapheme count past the end
+    pub fn grapheme_idx_at_col(&self, col: ColIdx) -> GraphemeIdx {
+        let mut fragment_end: ColIdx = 0;
+
+        self.fragments
+            .iter()
+            .position(|fragment| {
+                fragment_end = fragment_end.saturating_add(fragment.rendered_width.into());
+                fragment_end > col
+            })
+            .unwrap_or_else(|| self.grapheme_count())
     }
 
-    pub fn delete_last(&mut self) {
-        self.delete(self.grapheme_count().saturating_sub(1));
+    /// Returns the byte index at which a grapheme starts, or the line length past the last grapheme
+    pub fn byte_idx_of(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+        self.fragments
+            .get(grapheme_idx)
+            .map_or(self.string.len(), |fragment| fragment.start)
     }
 
-    pub fn width(&self) -> ColIdx {
-        self.width_until(self.grapheme_count())
+    /// Returns the index of the grapheme containing a byte, or the grapheme count past the end
+    pub fn grapheme_idx_of(&self, byte_idx: ByteIdx) -> GraphemeIdx {
+        if byte_idx >= self.string.len() {
+            return self.grapheme_count();
+        }
+
+        self.fragments
+            .iter()
+            .rposition(|fragment| fragment.start <= byte_idx)
+            .unwrap_or(0)
     }
 
-    pub fn search_forward(&self, query: &str, from_grapheme_idx: GraphemeIdx) -> Option<usize> {
+    pub fn search_forward(
+        &self,
+        query: &SearchQuery,
+        from_grapheme_idx: GraphemeIdx,
+    ) -> Option<usize> {
         debug_assert!(from_grapheme_idx <= self.grapheme_count());
         if from_grapheme_idx == self.grapheme_count() {
             return None;
@@ -215,7 +356,11 @@ impl Line {
             .map(|(_, grapheme_idx)| *grapheme_idx)
     }
 
-    pub fn search_backward(&self, query: &str, from_grapheme_idx: GraphemeIdx) -> Option<usize> {
+    pub fn search_backward(
+        &self,
+        query: &SearchQuery,
+        from_grapheme_idx: GraphemeIdx,
+    ) -> Option<usize> {
         debug_assert!(from_grapheme_idx <= self.grapheme_count());
         if from_grapheme_idx == 0 {
             return None;
@@ -0,0 +1,46 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Which numbers the gutter left of the text shows
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// No gutter at all
+    Off,
+    #[default]
+    Absolute,
+    /// Distance from the caret's line, which itself shows its absolute number
+    Relative,
+}
+
+impl LineNumbers {
+    /// Returns the next mode, for toggling through all of them
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Absolute,
+            Self::Absolute => Self::Relative,
+            Self::Relative => Self::Off,
+        }
+    }
+}
+
+impl Display for LineNumbers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Absolute => write!(f, "absolute"),
+            Self::Relative => write!(f, "relative"),
+        }
+    }
+}
+
+impl FromStr for LineNumbers {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "off" => Ok(Self::Off),
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            _ => Err(format!("Unknown line number mode: {value}")),
+        }
+    }
+}
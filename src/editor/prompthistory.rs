@@ -0,0 +1,159 @@
+use log::warn;
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    path::PathBuf,
+};
+
+const HISTORY_FILE_NAME: &str = "history";
+const HISTORY_SIZE: usize = 100;
+
+/// The kinds of values entered into prompts, each of which has its own history
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistoryKind {
+    Search,
+    Replacement,
+    FileName,
+}
+
+impl HistoryKind {
+    const ALL: [Self; 3] = [Self::Search, Self::Replacement, Self::FileName];
+
+    /// Name of the kind in the history file
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Search => "search",
+            Self::Replacement => "replacement",
+            Self::FileName => "filename",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Values previously entered into prompts, newest first, kept across sessions in
+/// `$XDG_STATE_HOME/hecto/history` (or `~/.local/state/hecto/history`).
+/// Each line of the file holds a kind name and a value, separated by a tab.
+#[derive(Default)]
+pub struct PromptHistory {
+    entries: HashMap<HistoryKind, VecDeque<String>>,
+    /// Entry currently recalled into the prompt, if any
+    position: Option<usize>,
+    /// What was typed into the prompt before recalling entries
+    draft: String,
+}
+
+impl PromptHistory {
+    /// Loads the history file, starting out empty if it can't be read
+    pub fn load() -> Self {
+        let mut history = Self::default();
+
+        if let Some(contents) = Self::path().and_then(|path| fs::read_to_string(path).ok()) {
+            // The file lists the oldest entries first
+            for line in contents.lines() {
+                match line
+                    .split_once('\t')
+                    .and_then(|(name, value)| Some((HistoryKind::from_name(name)?, value)))
+                {
+                    Some((kind, value)) => history.push(kind, value.to_string()),
+                    None => warn!("Ignoring malformed history line: {line}"),
+                }
+            }
+        }
+
+        history
+    }
+
+    /// Adds an entry as the newest of its kind and writes the history to disk
+    pub fn add(&mut self, kind: HistoryKind, entry: &str) {
+        self.reset_recall();
+        if entry.is_empty() {
+            return;
+        }
+
+        self.push(kind, entry.to_string());
+        self.save();
+    }
+
+    /// Recalls the next older entry. `current` is the prompt's value, which is
+    /// remembered when starting to recall so that `newer` can get back to it.
+    pub fn older(&mut self, kind: HistoryKind, current: &str) -> Option<&str> {
+        let entries = self.entries.get(&kind)?;
+        let position = self
+            .position
+            .map_or(0, |position| position.saturating_add(1));
+        let entry = entries.get(position)?;
+
+        if self.position.is_none() {
+            self.draft = current.to_string();
+        }
+        self.position = Some(position);
+        Some(entry)
+    }
+
+    /// Recalls the next newer entry, or what was typed before recalling once past the newest one
+    pub fn newer(&mut self, kind: HistoryKind) -> Option<&str> {
+        let position = self.position?;
+
+        if let Some(newer_position) = position.checked_sub(1) {
+            self.position = Some(newer_position);
+            self.entries
+                .get(&kind)
+                .and_then(|entries| entries.get(newer_position))
+                .map(String::as_str)
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Forgets which entry is recalled, so the next recall starts at the newest one again
+    pub fn reset_recall(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    fn push(&mut self, kind: HistoryKind, entry: String) {
+        let entries = self.entries.entry(kind).or_default();
+        entries.retain(|existing| *existing != entry);
+        entries.push_front(entry);
+        entries.truncate(HISTORY_SIZE);
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+
+        let mut contents = String::new();
+        for kind in HistoryKind::ALL {
+            if let Some(entries) = self.entries.get(&kind) {
+                for entry in entries.iter().rev() {
+                    contents.push_str(kind.name());
+                    contents.push('\t');
+                    contents.push_str(entry);
+                    contents.push('\n');
+                }
+            }
+        }
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, contents));
+        if let Err(err) = result {
+            warn!("Could not write history file: {err}");
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+            })
+            .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(HISTORY_FILE_NAME))
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+/// How letter case is taken into account when matching a search query
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMatching {
+    /// Case-insensitive, unless the query contains an uppercase letter
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseMatching {
+    /// Returns the next mode, for toggling through all of them
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Smart => Self::Sensitive,
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+        }
+    }
+}
+
+impl Display for CaseMatching {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Smart => write!(f, "smart case"),
+            Self::Sensitive => write!(f, "match case"),
+            Self::Insensitive => write!(f, "ignore case"),
+        }
+    }
+}
+
+/// How a search query is matched against the text
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Treats the query as a regular expression instead of literal text
+    pub regex: bool,
+    pub case: CaseMatching,
+    /// Only matches which are not part of a longer word
+    pub whole_word: bool,
+}
+
+impl Display for SearchOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.case)?;
+        if self.regex {
+            write!(f, ", regex")?;
+        }
+        if self.whole_word {
+            write!(f, ", whole word")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use super::searchoptions::{CaseMatching, SearchOptions};
+use crate::prelude::ByteIdx;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// A compiled search query. Literal queries are escaped, so every mode
+/// is matched the same way.
+pub struct SearchQuery {
+    regex: Regex,
+    whole_word: bool,
+    expands_captures: bool,
+}
+
+impl SearchQuery {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+
+        let case_insensitive = match options.case {
+            CaseMatching::Smart => !has_uppercase(query, options.regex),
+            CaseMatching::Sensitive => false,
+            CaseMatching::Insensitive => true,
+        };
+
+        Ok(Self {
+            regex: RegexBuilder::new(&pattern)
+                .case_insensitive(case_insensitive)
+                .build()?,
+            whole_word: options.whole_word,
+            expands_captures: options.regex,
+        })
+    }
+
+    /// Whether the haystack may contain a match. Quicker than `find_all`, to skip text
+    /// without any; text it is true for may still turn out to have none.
+    pub fn may_match(&self, haystack: &str) -> bool {
+        self.regex.is_match(haystack)
+    }
+
+    /// Returns the byte ranges of all non-empty matches starting within `range`.
+    /// The whole haystack is searched, so anchors and word boundaries see the surrounding text.
+    pub fn find_all(&self, haystack: &str, range: Range<ByteIdx>) -> Vec<Range<ByteIdx>> {
+        let mut result = Vec::new();
+        let mut start = range.start;
+
+        while start <= haystack.len() {
+            let Some(found) = self.regex.find_at(haystack, start) else {
+                break;
+            };
+
+            if found.start() >= range.end {
+                break;
+            }
+
+            if found.is_empty() {
+                // Skip ahead by one char to avoid finding the same empty match again
+                start = haystack
+                    .get(found.end()..)
+                    .and_then(|rest| rest.chars().next())
+                    .map_or(usize::MAX, |char| {
+                        found.end().saturating_add(char.len_utf8())
+                    });
+            } else {
+                if !self.whole_word || Self::is_whole_word(haystack, &found.range()) {
+                    result.push(found.range());
+                }
+                start = found.end();
+            }
+        }
+
+        result
+    }
+
+    /// Returns the text replacing the match at `range`. In regex mode, references to
+    /// capture groups such as `$1` are expanded, otherwise the replacement is used as is.
+    pub fn expand_replacement(
+        &self,
+        haystack: &str,
+        range: &Range<ByteIdx>,
+        replacement: &str,
+    ) -> String {
+        if !self.expands_captures {
+            return replacement.to_string();
+        }
+
+        let mut expanded = String::new();
+        if let Some(captures) = self.regex.captures_at(haystack, range.start) {
+            captures.expand(replacement, &mut expanded);
+        }
+
+        expanded
+    }
+
+    /// Checks that a match doesn't continue a word on either side. An edge of the match
+    /// only needs a boundary if it is a word character itself, so that `(in)` is found in `f(in)`.
+    fn is_whole_word(haystack: &str, range: &Range<ByteIdx>) -> bool {
+        let is_word_char = |char: char| char.is_alphanumeric() || char == '_';
+        let found = haystack.get(range.clone()).unwrap_or_default();
+
+        let before = haystack
+            .get(..range.start)
+            .and_then(|before| before.chars().next_back());
+        let after = haystack
+            .get(range.end..)
+            .and_then(|after| after.chars().next());
+
+        let starts_word = found.chars().next().is_some_and(is_word_char);
+        let ends_word = found.chars().next_back().is_some_and(is_word_char);
+
+        let continues_before = starts_word && before.is_some_and(is_word_char);
+        let continues_after = ends_word && after.is_some_and(is_word_char);
+
+        !continues_before && !continues_after
+    }
+
+    /// Describes why a query could not be compiled, in a single line
+    pub fn describe_error(err: &regex::Error) -> String {
+        let message = err.to_string();
+        let reason = message.lines().last().unwrap_or_default();
+        format!(
+            "Invalid pattern: {}",
+            reason.trim().trim_start_matches("error: ")
+        )
+    }
+}
+
+/// Whether a query has uppercase letters, for smart-case. In a regex, letters which are
+/// part of an escape, such as in `\W`, `\x4F` or `\p{Lu}`, don't count.
+fn has_uppercase(query: &str, is_regex: bool) -> bool {
+    if !is_regex {
+        return query.chars().any(char::is_uppercase);
+    }
+
+    let mut chars = query.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            if char.is_uppercase() {
+                return true;
+            }
+            continue;
+        }
+
+        // Escapes which take a name or code, as one or more letters, or in braces
+        let max_unbraced: usize = match chars.next() {
+            Some('p' | 'P') => 1,
+            Some('x') => 2,
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => continue,
+        };
+        if chars.next_if_eq(&'{').is_some() {
+            chars
+                .by_ref()
+                .take_while(|char| *char != '}')
+                .for_each(drop);
+        } else {
+            for _ in 0..max_unbraced {
+                if chars.next_if(char::is_ascii_alphanumeric).is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::has_uppercase;
+
+    #[test]
+    fn counts_uppercase_letters() {
+        assert!(has_uppercase("Foo", false));
+        assert!(has_uppercase("Foo", true));
+        assert!(!has_uppercase("foo", true));
+        // Escapes are taken literally outside of regex mode
+        assert!(has_uppercase("\\W", false));
+    }
+
+    #[test]
+    fn skips_letters_of_regex_escapes() {
+        assert!(!has_uppercase("\\W+\\S\\D\\B", true));
+        assert!(!has_uppercase("\\p{Lu}\\PL\\x4F\\x{1F600}\\u00C4", true));
+        assert!(has_uppercase("\\p{Lu}A", true));
+        assert!(has_uppercase("\\WA", true));
+        assert!(has_uppercase("\\\\A", true));
+    }
+}
@@ -33,6 +33,18 @@ impl From<AnnotationType> for Attribute {
                     b: 153,
                 }),
             },
+            AnnotationType::Selection => Self {
+                foreground: Some(Color::Rgb {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                }),
+                background: Some(Color::Rgb {
+                    r: 70,
+                    g: 90,
+                    b: 150,
+                }),
+            },
             AnnotationType::Number => Self {
                 foreground: Some(Color::Rgb {
                     r: 255,
@@ -61,10 +73,14 @@ impl From<AnnotationType> for Attribute {
                 foreground: Some(Color::DarkMagenta),
                 background: None,
             },
-            AnnotationType::Comment => Self {
+            AnnotationType::Comment | AnnotationType::LineNumber => Self {
                 foreground: Some(Color::DarkGrey),
                 background: None,
             },
+            AnnotationType::CurrentLineNumber => Self {
+                foreground: Some(Color::White),
+                background: None,
+            },
         }
     }
 }
@@ -1,17 +1,27 @@
 mod attribute;
+mod osc52;
 use super::annotatedstring::AnnotatedString;
 use crate::prelude::*;
 use attribute::Attribute;
 use crossterm::{
-    Command, cursor, queue,
+    Command, cursor,
+    event::{
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture,
+    },
+    queue,
     style::{
         self,
-        Attribute::{Reset, Reverse},
+        Attribute::{NoReverse, Reset, Reverse},
         ResetColor,
     },
     terminal,
 };
-use std::io::{self, Error, Write};
+use std::{
+    io::{self, Error, Write},
+    ops::Range,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub struct Terminal;
 
@@ -20,6 +30,8 @@ impl Terminal {
         terminal::enable_raw_mode()?;
         Self::enter_alternate_screen()?;
         Self::disable_line_wrap()?;
+        Self::enable_bracketed_paste()?;
+        Self::enable_focus_change()?;
         Self::clear_screen()?;
         Self::execute()?;
 
@@ -27,6 +39,9 @@ impl Terminal {
     }
 
     pub fn terminate() -> Result<(), Error> {
+        Self::disable_mouse_capture()?;
+        Self::disable_focus_change()?;
+        Self::disable_bracketed_paste()?;
         Self::leave_alternate_screen()?;
         Self::enable_line_wrap()?;
         Self::show_caret()?;
@@ -73,6 +88,16 @@ impl Terminal {
         Self::queue_command(style::Print(s))
     }
 
+    /// Places text on the system clipboard of the terminal emulator through OSC 52.
+    /// Returns `false` if it was longer than `max_bytes` and not sent.
+    pub fn copy_to_clipboard(text: &str, max_bytes: usize) -> Result<bool, Error> {
+        let Some(sequence) = osc52::clipboard_sequence(text, max_bytes) else {
+            return Ok(false);
+        };
+        Self::print(&sequence)?;
+        Ok(true)
+    }
+
     pub fn set_title(to: &str) -> Result<(), Error> {
         Self::queue_command(terminal::SetTitle(to))
     }
@@ -89,15 +114,48 @@ impl Terminal {
 
     pub fn print_inverted_row(row: RowIdx, line_text: &str) -> Result<(), Error> {
         let width = Self::size()?.width;
-        Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}",))
+        Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}"))
     }
 
-    pub fn print_annotated_row(
+    /// Prints a row in inverse video, except for the given span of it, which stands out by being printed normally
+    pub fn print_inverted_row_except(
         row: RowIdx,
+        line_text: &str,
+        normal: Range<ByteIdx>,
+    ) -> Result<(), Error> {
+        let width = Self::size()?.width;
+        let line_text = truncate_to_width(line_text, width);
+        let normal = normal.start.min(line_text.len())..normal.end.min(line_text.len());
+        let before = line_text.get(..normal.start).unwrap_or_default();
+        let span = line_text.get(normal.clone()).unwrap_or_default();
+        let after = line_text.get(normal.end..).unwrap_or_default();
+        let padding = width.saturating_sub(line_text.width());
+
+        Self::print_row(
+            row,
+            &format!(
+                "{Reverse}{before}{NoReverse}{span}{Reverse}{after}{}{Reset}",
+                " ".repeat(padding)
+            ),
+        )
+    }
+
+    /// Prints text at a position, padded with spaces to the given width. Unlike
+    /// `print_row`, the rest of the row is left alone, so it works for panes side by side.
+    pub fn print_at(position: Position, text: &str, width: usize) -> Result<(), Error> {
+        Self::move_caret_to(&position)?;
+        Self::print(text)?;
+        Self::print_padding(text.width(), width)
+    }
+
+    /// Prints an annotated string at a position, padded with spaces to the given width
+    pub fn print_annotated_at(
+        position: Position,
         annotated_string: &AnnotatedString,
+        width: usize,
     ) -> Result<(), Error> {
-        Self::move_caret_to(&Position { row, col: 0 })?;
-        Self::clear_line()?;
+        Self::move_caret_to(&position)?;
+        let mut printed_width: usize = 0;
         annotated_string
             .into_iter()
             .try_for_each(|part| -> Result<(), Error> {
@@ -108,10 +166,19 @@ impl Terminal {
 
                 Self::print(part.string)?;
                 Self::reset_color()?;
+                printed_width = printed_width.saturating_add(part.string.width());
 
                 Ok(())
             })?;
 
+        Self::print_padding(printed_width, width)
+    }
+
+    fn print_padding(printed_width: usize, width: usize) -> Result<(), Error> {
+        let padding = width.saturating_sub(printed_width);
+        if padding > 0 {
+            Self::print(&" ".repeat(padding))?;
+        }
         Ok(())
     }
 
@@ -148,7 +215,46 @@ impl Terminal {
         Self::queue_command(terminal::DisableLineWrap)
     }
 
+    /// Makes the terminal deliver pasted text as a single event instead of as key presses
+    fn enable_bracketed_paste() -> Result<(), Error> {
+        Self::queue_command(EnableBracketedPaste)
+    }
+
+    fn disable_bracketed_paste() -> Result<(), Error> {
+        Self::queue_command(DisableBracketedPaste)
+    }
+
+    /// Makes the terminal report when it gains or loses focus
+    fn enable_focus_change() -> Result<(), Error> {
+        Self::queue_command(EnableFocusChange)
+    }
+
+    fn disable_focus_change() -> Result<(), Error> {
+        Self::queue_command(DisableFocusChange)
+    }
+
+    /// Makes the terminal report mouse clicks and scrolling instead of handling them itself
+    pub fn enable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(EnableMouseCapture)
+    }
+
+    fn disable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(DisableMouseCapture)
+    }
+
     fn queue_command(command: impl Command) -> Result<(), Error> {
         queue!(io::stdout(), command)
     }
 }
+
+/// Cuts text down to the given width in columns
+fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut text_width: usize = 0;
+    for (idx, ch) in text.char_indices() {
+        text_width = text_width.saturating_add(ch.width().unwrap_or(0));
+        if text_width > width {
+            return text.get(..idx).unwrap_or_default();
+        }
+    }
+    text
+}
@@ -0,0 +1,32 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Builds the OSC 52 escape sequence which asks the terminal emulator to put `text`
+/// on the system clipboard. This works over SSH as well, since it travels with the output.
+/// Returns `None` for text longer than `max_bytes`, which terminals may drop or choke on.
+pub fn clipboard_sequence(text: &str, max_bytes: usize) -> Option<String> {
+    (text.len() <= max_bytes).then(|| format!("\x1b]52;c;{}\x07", STANDARD.encode(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clipboard_sequence;
+
+    #[test]
+    fn encodes_text_in_the_sequence() {
+        assert_eq!(
+            clipboard_sequence("hello", 5).map(String::into_bytes),
+            Some(b"\x1b]52;c;aGVsbG8=\x07".to_vec())
+        );
+        assert_eq!(
+            clipboard_sequence("", 0).map(String::into_bytes),
+            Some(b"\x1b]52;c;\x07".to_vec())
+        );
+    }
+
+    #[test]
+    fn skips_text_over_the_limit() {
+        assert_eq!(clipboard_sequence("hello", 4), None);
+        // The limit is in bytes, not characters
+        assert_eq!(clipboard_sequence("é", 1), None);
+    }
+}
@@ -1,5 +1,10 @@
-use crate::editor::{command::Edit, line::Line, terminal::Terminal};
-use crate::prelude::{ColIdx, RowIdx, Size};
+use crate::editor::{
+    command::{Edit, Move},
+    line::Line,
+    terminal::Terminal,
+};
+use crate::prelude::{ColIdx, GraphemeIdx, Position, Size};
+use unicode_width::UnicodeWidthStr;
 
 use super::UIComponent;
 
@@ -8,34 +13,125 @@ pub struct CommandBar {
     needs_redraw: bool,
     prompt: String,
     value: Line,
+    /// Position of the caret within the value
+    caret: GraphemeIdx,
+    /// Column of the value shown right after the prompt
+    scroll_offset: ColIdx,
+    status: String,
     size: Size,
 }
 
 impl CommandBar {
     pub fn set_prompt(&mut self, prompt: &str) {
         self.prompt = prompt.to_string();
+        self.scroll_caret_into_view();
         self.set_needs_redraw(true);
     }
 
+    /// Sets a short text shown at the right end of the bar, such as an error
+    pub fn set_status(&mut self, status: &str) {
+        if status != self.status {
+            self.status = status.to_string();
+            self.set_needs_redraw(true);
+        }
+    }
+
     pub fn clear_value(&mut self) {
-        self.value = Line::default();
+        self.set_value("");
+    }
+
+    /// Replaces the value, placing the caret at its end
+    pub fn set_value(&mut self, value: &str) {
+        self.value = Line::from(value);
+        self.caret = self.value.grapheme_count();
+        self.scroll_offset = 0;
+        self.scroll_caret_into_view();
         self.set_needs_redraw(true);
     }
 
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
-            Edit::Insert(c) => self.value.append_char(c),
-            Edit::DeleteBackward => self.value.delete_last(),
-            Edit::InsertNewLine | Edit::Delete => {}
+            Edit::Insert(c) => self.insert_at_caret(&c.to_string()),
+            Edit::DeleteBackward => {
+                if let Some(previous) = self.caret.checked_sub(1) {
+                    self.caret = previous;
+                    self.value.delete(previous);
+                }
+            }
+            Edit::Delete => self.value.delete(self.caret),
+            Edit::DeleteWordBackward => {
+                let word_start = self.value.word_start_before(self.caret);
+                self.value.remove_bytes(
+                    self.value.byte_idx_of(word_start)..self.value.byte_idx_of(self.caret),
+                );
+                self.caret = word_start;
+            }
+            Edit::InsertNewLine
+            | Edit::Undo
+            | Edit::Redo
+            | Edit::Copy
+            | Edit::Cut
+            | Edit::Paste
+            | Edit::PasteOlder => {}
         }
 
+        self.scroll_caret_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    pub fn handle_move_command(&mut self, command: Move) {
+        self.caret = match command {
+            Move::Left => self.caret.saturating_sub(1),
+            Move::Right => self
+                .caret
+                .saturating_add(1)
+                .min(self.value.grapheme_count()),
+            Move::StartOfLine => 0,
+            Move::EndOfLine => self.value.grapheme_count(),
+            Move::Up | Move::Down | Move::PageUp | Move::PageDown => return,
+        };
+
+        self.scroll_caret_into_view();
         self.set_needs_redraw(true);
     }
 
+    /// Inserts pasted text at the caret, dropping line breaks and other control characters
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|char| !char.is_control()).collect();
+        self.insert_at_caret(&text);
+        self.scroll_caret_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    fn insert_at_caret(&mut self, text: &str) {
+        let at = self.value.byte_idx_of(self.caret);
+        self.value.insert_str_at_byte(at, text);
+        // Combining characters may merge with the grapheme before them,
+        // so the caret is placed after the inserted bytes rather than counted forward
+        self.caret = self.value.grapheme_idx_of(at.saturating_add(text.len()));
+    }
+
+    /// Number of columns available for the value, right of the prompt
+    fn value_area_width(&self) -> ColIdx {
+        self.size.width.saturating_sub(self.prompt.width())
+    }
+
+    fn scroll_caret_into_view(&mut self) {
+        let caret_col = self.value.width_until(self.caret);
+        let area_width = self.value_area_width();
+
+        if caret_col < self.scroll_offset {
+            self.scroll_offset = caret_col;
+        } else if caret_col >= self.scroll_offset.saturating_add(area_width) {
+            self.scroll_offset = caret_col.saturating_sub(area_width).saturating_add(1);
+        }
+    }
+
     pub fn caret_position_col(&self) -> ColIdx {
         self.prompt
-            .len()
-            .saturating_add(self.value.grapheme_count())
+            .width()
+            .saturating_add(self.value.width_until(self.caret))
+            .saturating_sub(self.scroll_offset)
             .min(self.size.width)
     }
 
@@ -45,27 +141,33 @@ impl CommandBar {
 }
 
 impl UIComponent for CommandBar {
-    fn draw(&mut self, origin: RowIdx) -> Result<(), std::io::Error> {
-        let area_for_value = self.size.width.saturating_sub(self.prompt.len());
-        let value_end = self.value.width();
-        let value_start = value_end.saturating_sub(area_for_value);
-        let message = format!(
+    fn draw(&mut self, origin: Position) -> Result<(), std::io::Error> {
+        let value_end = self.scroll_offset.saturating_add(self.value_area_width());
+        let mut message = format!(
             "{}{}",
             self.prompt,
-            self.value.get_visible_graphemes(value_start..value_end)
+            self.value
+                .get_visible_graphemes(self.scroll_offset..value_end)
         );
 
-        let to_print = if message.len() <= self.size.width {
+        // The status is only shown when it fits next to the value, with a space in between
+        let remainder_len = self.size.width.saturating_sub(message.width());
+        if !self.status.is_empty() && self.status.width() < remainder_len {
+            message = format!("{message}{:>remainder_len$}", self.status);
+        }
+
+        let to_print = if message.width() <= self.size.width {
             message
         } else {
             String::new()
         };
 
-        Terminal::print_row(origin, &to_print)
+        Terminal::print_row(origin.row, &to_print)
     }
 
     fn set_size(&mut self, size: Size) {
         self.size = size;
+        self.scroll_caret_into_view();
     }
 
     fn set_needs_redraw(&mut self, value: bool) {
@@ -1,7 +1,7 @@
 use crate::editor::Size;
 use crate::editor::Terminal;
 use crate::editor::uicomponents::UIComponent;
-use crate::prelude::RowIdx;
+use crate::prelude::Position;
 use std::{
     io::Error,
     time::{Duration, Instant},
@@ -60,7 +60,7 @@ impl UIComponent for MessageBar {
 
     fn set_size(&mut self, _: Size) {}
 
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
         let message = if self.current_message.is_expired() {
             self.cleared_after_expiry = true;
             ""
@@ -68,6 +68,6 @@ impl UIComponent for MessageBar {
             &self.current_message.content
         };
 
-        Terminal::print_row(origin, message)
+        Terminal::print_row(origin.row, message)
     }
 }
@@ -1,11 +1,15 @@
 mod commandbar;
 mod messagebar;
+mod panes;
 mod statusbar;
+mod tabbar;
 mod uicomponent;
 mod view;
 
 pub use commandbar::CommandBar;
 pub use messagebar::MessageBar;
+pub use panes::{Panes, SplitDirection};
 pub use statusbar::StatusBar;
+pub use tabbar::{Tab, TabBar};
 pub use uicomponent::UIComponent;
-pub use view::View;
+pub use view::{Buffer, MatchCount, SharedBuffer, View};
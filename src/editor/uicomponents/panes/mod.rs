@@ -0,0 +1,253 @@
+use super::UIComponent;
+use super::view::{SharedBuffer, View};
+use crate::editor::command::Move;
+use crate::prelude::*;
+use pane::{Leaf, Pane};
+use std::io::Error;
+mod pane;
+mod splitdirection;
+pub use splitdirection::SplitDirection;
+
+/// Panes need at least this many rows or columns to be split
+const MIN_SPLIT_LEN: usize = 3;
+
+/// The text area, divided into panes which each show a view. Several views
+/// can show the same buffer, each with its own caret and scroll position.
+#[derive(Default)]
+pub struct Panes {
+    root: Pane,
+    /// View of the focused pane, kept here rather than in its leaf
+    focused: View,
+    focused_idx: usize,
+    size: Size,
+    needs_redraw: bool,
+}
+
+impl Panes {
+    pub const fn view(&self) -> &View {
+        &self.focused
+    }
+
+    pub const fn view_mut(&mut self) -> &mut View {
+        &mut self.focused
+    }
+
+    /// All views, the focused one last
+    pub fn views_mut(&mut self) -> impl Iterator<Item = &mut View> {
+        let mut leaves = Vec::new();
+        self.root.collect_leaves_mut(&mut leaves);
+
+        leaves
+            .into_iter()
+            .filter_map(|leaf| leaf.view.as_deref_mut())
+            .chain(std::iter::once(&mut self.focused))
+    }
+
+    /// Position of the caret, relative to the area of all panes
+    pub fn caret_position(&self) -> Position {
+        let offset = self
+            .root
+            .leaf(self.focused_idx)
+            .map(|leaf| leaf.offset)
+            .unwrap_or_default();
+
+        offset.saturating_add(self.focused.caret_position())
+    }
+
+    /// Makes every pane showing `old` show `new` instead
+    pub fn replace_buffer(&mut self, old: &SharedBuffer, new: &SharedBuffer) {
+        for view in self.views_mut() {
+            if view.shows_buffer(old) {
+                view.set_buffer(SharedBuffer::clone(new));
+            }
+        }
+    }
+
+    /// Splits the focused pane in two, both showing its buffer, and focuses the new one.
+    /// Returns whether there was enough room to split it.
+    pub fn split(&mut self, direction: SplitDirection) -> bool {
+        let Some(leaf) = self.root.leaf(self.focused_idx) else {
+            return false;
+        };
+        let len = match direction {
+            SplitDirection::Vertical => leaf.size.width,
+            SplitDirection::Horizontal => leaf.size.height,
+        };
+        if len < MIN_SPLIT_LEN.saturating_mul(2).saturating_add(1) {
+            return false;
+        }
+
+        let view = self.focused.duplicate();
+        self.root.split_leaf(self.focused_idx, direction, view);
+        self.focus(self.focused_idx.saturating_add(1));
+        self.layout();
+
+        true
+    }
+
+    /// Closes the focused pane, giving its space to the pane next to it.
+    /// Returns whether it was closed, which the only pane can't be.
+    pub fn close(&mut self) -> bool {
+        if !self.root.remove_leaf(self.focused_idx) {
+            return false;
+        }
+
+        self.focused_idx = self
+            .focused_idx
+            .min(self.root.leaf_count().saturating_sub(1));
+        if let Some(view) = self
+            .root
+            .leaf_mut(self.focused_idx)
+            .and_then(|leaf| leaf.view.take())
+        {
+            self.focused = *view;
+        }
+        self.layout();
+
+        true
+    }
+
+    /// Focuses the pane next to the focused one in the given direction, preferring
+    /// the one beside the caret. Returns whether there was one.
+    pub fn focus_towards(&mut self, direction: Move) -> bool {
+        let Some(focused) = self.root.leaf(self.focused_idx) else {
+            return false;
+        };
+        let caret = self.caret_position();
+        let Leaf { offset, size, .. } = *focused;
+
+        // A point just across the divider, which is one row or column wide
+        let target = match direction {
+            Move::Left => offset
+                .col
+                .checked_sub(2)
+                .map(|col| Position { col, ..caret }),
+            Move::Right => Some(Position {
+                col: offset.col.saturating_add(size.width).saturating_add(1),
+                ..caret
+            }),
+            Move::Up => offset
+                .row
+                .checked_sub(2)
+                .map(|row| Position { row, ..caret }),
+            Move::Down => Some(Position {
+                row: offset.row.saturating_add(size.height).saturating_add(1),
+                ..caret
+            }),
+            _ => None,
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        let is_horizontal = matches!(direction, Move::Left | Move::Right);
+
+        let mut leaves = Vec::new();
+        self.root.collect_leaves(&mut leaves);
+        let closest = leaves
+            .iter()
+            .enumerate()
+            .filter(|(_, leaf)| {
+                if is_horizontal {
+                    span_distance(target.col, leaf.offset.col, leaf.size.width) == 0
+                } else {
+                    span_distance(target.row, leaf.offset.row, leaf.size.height) == 0
+                }
+            })
+            .min_by_key(|(_, leaf)| {
+                if is_horizontal {
+                    span_distance(target.row, leaf.offset.row, leaf.size.height)
+                } else {
+                    span_distance(target.col, leaf.offset.col, leaf.size.width)
+                }
+            })
+            .map(|(idx, _)| idx);
+
+        closest.is_some_and(|idx| self.focus(idx))
+    }
+
+    /// Grows or shrinks the focused pane. Returns whether it was resized.
+    pub fn resize_focused(&mut self, grow: bool) -> bool {
+        let is_resized = self.root.resize_leaf(self.focused_idx, grow);
+        if is_resized {
+            self.layout();
+        }
+        is_resized
+    }
+
+    fn focus(&mut self, idx: usize) -> bool {
+        if idx == self.focused_idx {
+            return false;
+        }
+        let Some(view) = self.root.leaf_mut(idx).and_then(|leaf| leaf.view.take()) else {
+            return false;
+        };
+
+        let previous = std::mem::replace(&mut self.focused, *view);
+        if let Some(leaf) = self.root.leaf_mut(self.focused_idx) {
+            leaf.view = Some(Box::new(previous));
+        }
+        self.focused_idx = idx;
+
+        true
+    }
+
+    fn layout(&mut self) {
+        self.root.layout(Position::default(), self.size);
+
+        let mut leaves = Vec::new();
+        self.root.collect_leaves_mut(&mut leaves);
+        for leaf in leaves {
+            let view = leaf.view.as_deref_mut().unwrap_or(&mut self.focused);
+            view.resize(leaf.size);
+        }
+
+        self.needs_redraw = true;
+    }
+}
+
+/// How far a coordinate is outside of a span, or 0 if it is inside
+const fn span_distance(at: usize, start: usize, len: usize) -> usize {
+    let end = start.saturating_add(len);
+    if at < start {
+        start.saturating_sub(at)
+    } else if at >= end {
+        at.saturating_sub(end).saturating_add(1)
+    } else {
+        0
+    }
+}
+
+impl UIComponent for Panes {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        if self.needs_redraw {
+            self.root.draw_dividers(origin)?;
+        }
+
+        let mut leaves = Vec::new();
+        self.root.collect_leaves_mut(&mut leaves);
+        for leaf in leaves {
+            let view = leaf.view.as_deref_mut().unwrap_or(&mut self.focused);
+            view.render(origin.saturating_add(leaf.offset));
+        }
+
+        Ok(())
+    }
+
+    fn set_size(&mut self, to: Size) {
+        self.size = to;
+        self.layout();
+    }
+
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+        if value {
+            for view in self.views_mut() {
+                view.set_needs_redraw(true);
+            }
+        }
+    }
+
+    fn needs_redraw(&mut self) -> bool {
+        self.needs_redraw || self.views_mut().any(UIComponent::needs_redraw)
+    }
+}
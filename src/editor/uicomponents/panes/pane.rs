@@ -0,0 +1,306 @@
+use super::splitdirection::SplitDirection;
+use crate::editor::terminal::Terminal;
+use crate::editor::uicomponents::View;
+use crate::prelude::*;
+use std::io::Error;
+
+/// Share of a split's space its first pane starts with, in percent
+const DEFAULT_RATIO: usize = 50;
+const MIN_RATIO: usize = 10;
+const MAX_RATIO: usize = 90;
+/// How much resizing a pane changes the share of its split, in percent
+const RATIO_STEP: usize = 5;
+
+/// A pane showing a view
+#[derive(Default)]
+pub struct Leaf {
+    /// The view, unless the pane is focused: `Panes` keeps the focused view itself
+    pub view: Option<Box<View>>,
+    /// Where the pane is, relative to the area of all panes
+    pub offset: Position,
+    pub size: Size,
+}
+
+/// An area divided into two panes by a line
+pub struct Split {
+    direction: SplitDirection,
+    /// Share of the space given to the first pane, in percent
+    ratio: usize,
+    first: Box<Pane>,
+    second: Box<Pane>,
+    /// Where the dividing line starts, relative to the area of all panes
+    divider_offset: Position,
+    divider_len: usize,
+}
+
+/// Node of the pane layout: a single pane, or an area split into two.
+/// Panes are numbered in order, from the first pane of each split to the second.
+pub enum Pane {
+    Leaf(Leaf),
+    Split(Split),
+}
+
+impl Default for Pane {
+    fn default() -> Self {
+        Self::Leaf(Leaf::default())
+    }
+}
+
+impl Pane {
+    pub const fn is_leaf(&self) -> bool {
+        matches!(self, Self::Leaf(_))
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Self::Leaf(_) => 1,
+            Self::Split(split) => split
+                .first
+                .leaf_count()
+                .saturating_add(split.second.leaf_count()),
+        }
+    }
+
+    pub fn leaf(&self, idx: usize) -> Option<&Leaf> {
+        match self {
+            Self::Leaf(leaf) => (idx == 0).then_some(leaf),
+            Self::Split(split) => {
+                let first_count = split.first.leaf_count();
+                if idx < first_count {
+                    split.first.leaf(idx)
+                } else {
+                    split.second.leaf(idx.saturating_sub(first_count))
+                }
+            }
+        }
+    }
+
+    pub fn leaf_mut(&mut self, idx: usize) -> Option<&mut Leaf> {
+        match self {
+            Self::Leaf(leaf) => (idx == 0).then_some(leaf),
+            Self::Split(split) => {
+                let first_count = split.first.leaf_count();
+                if idx < first_count {
+                    split.first.leaf_mut(idx)
+                } else {
+                    split.second.leaf_mut(idx.saturating_sub(first_count))
+                }
+            }
+        }
+    }
+
+    pub fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Leaf>) {
+        match self {
+            Self::Leaf(leaf) => leaves.push(leaf),
+            Self::Split(split) => {
+                split.first.collect_leaves(leaves);
+                split.second.collect_leaves(leaves);
+            }
+        }
+    }
+
+    pub fn collect_leaves_mut<'a>(&'a mut self, leaves: &mut Vec<&'a mut Leaf>) {
+        match self {
+            Self::Leaf(leaf) => leaves.push(leaf),
+            Self::Split(split) => {
+                split.first.collect_leaves_mut(leaves);
+                split.second.collect_leaves_mut(leaves);
+            }
+        }
+    }
+
+    /// Splits the pane at `idx` in two. It becomes the first of the two, `view` is shown in the second.
+    pub fn split_leaf(&mut self, idx: usize, direction: SplitDirection, view: View) {
+        match self {
+            Self::Leaf(_) if idx == 0 => {
+                let first = std::mem::take(self);
+                *self = Self::Split(Split {
+                    direction,
+                    ratio: DEFAULT_RATIO,
+                    first: Box::new(first),
+                    second: Box::new(Self::Leaf(Leaf {
+                        view: Some(Box::new(view)),
+                        ..Leaf::default()
+                    })),
+                    divider_offset: Position::default(),
+                    divider_len: 0,
+                });
+            }
+            Self::Leaf(_) => {}
+            Self::Split(split) => {
+                let first_count = split.first.leaf_count();
+                if idx < first_count {
+                    split.first.split_leaf(idx, direction, view);
+                } else {
+                    split
+                        .second
+                        .split_leaf(idx.saturating_sub(first_count), direction, view);
+                }
+            }
+        }
+    }
+
+    /// Removes the pane at `idx`, giving its space to the other pane of its split.
+    /// Returns whether it was removed, which the only pane can't be.
+    pub fn remove_leaf(&mut self, idx: usize) -> bool {
+        let Self::Split(split) = self else {
+            return false;
+        };
+
+        let first_count = split.first.leaf_count();
+        let remaining = if idx == 0 && split.first.is_leaf() {
+            &mut split.second
+        } else if idx == first_count && split.second.is_leaf() {
+            &mut split.first
+        } else if idx < first_count {
+            return split.first.remove_leaf(idx);
+        } else {
+            return split.second.remove_leaf(idx.saturating_sub(first_count));
+        };
+
+        let remaining = std::mem::take(remaining.as_mut());
+        *self = remaining;
+        true
+    }
+
+    /// Grows or shrinks the pane at `idx` by changing the share of the split it is in.
+    /// Returns whether it was resized.
+    pub fn resize_leaf(&mut self, idx: usize, grow: bool) -> bool {
+        let Self::Split(split) = self else {
+            return false;
+        };
+
+        let first_count = split.first.leaf_count();
+        let is_in_first = idx < first_count;
+        let child = if is_in_first {
+            &mut split.first
+        } else {
+            &mut split.second
+        };
+        if !child.is_leaf() {
+            let child_idx = if is_in_first {
+                idx
+            } else {
+                idx.saturating_sub(first_count)
+            };
+            return child.resize_leaf(child_idx, grow);
+        }
+
+        let ratio = if is_in_first == grow {
+            split.ratio.saturating_add(RATIO_STEP).min(MAX_RATIO)
+        } else {
+            split.ratio.saturating_sub(RATIO_STEP).max(MIN_RATIO)
+        };
+        let is_resized = ratio != split.ratio;
+        split.ratio = ratio;
+        is_resized
+    }
+
+    /// Divides the given area among the panes
+    pub fn layout(&mut self, offset: Position, size: Size) {
+        match self {
+            Self::Leaf(leaf) => {
+                leaf.offset = offset;
+                leaf.size = size;
+            }
+            Self::Split(split) => split.layout(offset, size),
+        }
+    }
+
+    pub fn draw_dividers(&self, origin: Position) -> Result<(), Error> {
+        let Self::Split(split) = self else {
+            return Ok(());
+        };
+
+        let start = origin.saturating_add(split.divider_offset);
+        match split.direction {
+            SplitDirection::Vertical => {
+                for row in 0..split.divider_len {
+                    let position = Position {
+                        row: start.row.saturating_add(row),
+                        col: start.col,
+                    };
+                    Terminal::print_at(position, "│", 1)?;
+                }
+            }
+            SplitDirection::Horizontal => {
+                let divider = "─".repeat(split.divider_len);
+                Terminal::print_at(start, &divider, split.divider_len)?;
+            }
+        }
+
+        split.first.draw_dividers(origin)?;
+        split.second.draw_dividers(origin)
+    }
+}
+
+impl Split {
+    fn layout(&mut self, offset: Position, size: Size) {
+        match self.direction {
+            SplitDirection::Vertical => {
+                let first_width = self.first_len(size.width);
+                let divider_col = offset.col.saturating_add(first_width);
+
+                self.first.layout(
+                    offset,
+                    Size {
+                        width: first_width,
+                        ..size
+                    },
+                );
+                self.second.layout(
+                    Position {
+                        col: divider_col.saturating_add(1),
+                        ..offset
+                    },
+                    Size {
+                        width: size.width.saturating_sub(first_width).saturating_sub(1),
+                        ..size
+                    },
+                );
+                self.divider_offset = Position {
+                    col: divider_col,
+                    ..offset
+                };
+                self.divider_len = size.height;
+            }
+            SplitDirection::Horizontal => {
+                let first_height = self.first_len(size.height);
+                let divider_row = offset.row.saturating_add(first_height);
+
+                self.first.layout(
+                    offset,
+                    Size {
+                        height: first_height,
+                        ..size
+                    },
+                );
+                self.second.layout(
+                    Position {
+                        row: divider_row.saturating_add(1),
+                        ..offset
+                    },
+                    Size {
+                        height: size.height.saturating_sub(first_height).saturating_sub(1),
+                        ..size
+                    },
+                );
+                self.divider_offset = Position {
+                    row: divider_row,
+                    ..offset
+                };
+                self.divider_len = size.width;
+            }
+        }
+    }
+
+    /// Length of the first pane along the split's direction, out of the given
+    /// length minus the row or column taken by the divider
+    fn first_len(&self, len: usize) -> usize {
+        let available = len.saturating_sub(1);
+        available
+            .saturating_mul(self.ratio)
+            .div_ceil(100)
+            .min(available)
+    }
+}
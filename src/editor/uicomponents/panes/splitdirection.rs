@@ -0,0 +1,8 @@
+/// How a split arranges its two panes
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SplitDirection {
+    /// Side by side, divided by a column
+    Vertical,
+    /// Stacked, divided by a row
+    Horizontal,
+}
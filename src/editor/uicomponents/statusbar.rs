@@ -2,7 +2,7 @@ use crate::editor::Size;
 use crate::editor::documentstatus::DocumentStatus;
 use crate::editor::terminal::Terminal;
 use crate::editor::uicomponents::UIComponent;
-use crate::prelude::RowIdx;
+use crate::prelude::Position;
 use std::io::Error;
 
 #[derive(Default)]
@@ -22,7 +22,7 @@ impl StatusBar {
 }
 
 impl UIComponent for StatusBar {
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
         let line_count = self.current_status.line_count_to_string();
         let modified_indicator = self.current_status.modified_indicator_to_string();
 
@@ -34,8 +34,9 @@ impl UIComponent for StatusBar {
         let position_indicator = self.current_status.position_indicator_to_string();
         let reminder_len = self.size.width.saturating_sub(beginning.len());
         let file_type = self.current_status.file_type_to_string();
+        let file_format = self.current_status.file_format_to_string();
 
-        let back_part = format!("{file_type} | {position_indicator}");
+        let back_part = format!("{file_type} | {file_format} | {position_indicator}");
         let status = format!("{beginning}{back_part:>reminder_len$}");
 
         let to_print = if status.len() <= self.size.width {
@@ -44,7 +45,7 @@ impl UIComponent for StatusBar {
             String::new()
         };
 
-        Terminal::print_inverted_row(origin, &to_print)
+        Terminal::print_inverted_row(origin.row, &to_print)
     }
 
     fn set_size(&mut self, to: Size) {
@@ -0,0 +1,142 @@
+use crate::editor::Size;
+use crate::editor::terminal::Terminal;
+use crate::editor::uicomponents::UIComponent;
+use crate::prelude::{ColIdx, Position};
+use std::{io::Error, ops::Range};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Longer file names are cut short, so a single tab can't take up the whole bar
+const MAX_NAME_WIDTH: usize = 20;
+
+#[derive(PartialEq, Eq)]
+pub struct Tab {
+    pub name: String,
+    pub is_modified: bool,
+}
+
+impl Tab {
+    fn label(&self) -> String {
+        let modified_indicator = if self.is_modified { "+" } else { "" };
+        format!(" {}{modified_indicator} ", truncate_name(&self.name))
+    }
+}
+
+fn truncate_name(name: &str) -> String {
+    if name.width() <= MAX_NAME_WIDTH {
+        return name.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut width: usize = 0;
+    for ch in name.chars() {
+        width = width.saturating_add(ch.width().unwrap_or(0));
+        // Leaves room for the ellipsis
+        if width >= MAX_NAME_WIDTH {
+            break;
+        }
+        truncated.push(ch);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// A row of tabs, one per open buffer, above the panes. When they don't fit,
+/// they scroll to keep the current one visible, with `<` and `>` marking hidden ones.
+#[derive(Default)]
+pub struct TabBar {
+    tabs: Vec<Tab>,
+    current: usize,
+    first_visible: usize,
+    /// Columns of the tabs shown when the bar was last drawn, with their indices
+    shown: Vec<(Range<ColIdx>, usize)>,
+    size: Size,
+    needs_redraw: bool,
+}
+
+impl TabBar {
+    pub fn update_tabs(&mut self, tabs: Vec<Tab>, current: usize) {
+        if tabs != self.tabs || current != self.current {
+            self.tabs = tabs;
+            self.current = current;
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Returns the index of the tab shown at a column, if any
+    pub fn tab_at(&self, col: ColIdx) -> Option<usize> {
+        self.shown
+            .iter()
+            .find(|(cols, _)| cols.contains(&col))
+            .map(|(_, idx)| *idx)
+    }
+
+    /// Scrolls as little as possible for the current tab to fit in the given width
+    fn scroll_to_current(&mut self, labels: &[String], width: usize) {
+        self.first_visible = self.first_visible.min(self.current);
+
+        while self.first_visible < self.current {
+            let shown_width: usize = labels
+                .get(self.first_visible..=self.current)
+                .unwrap_or_default()
+                .iter()
+                .map(|label| label.width())
+                .sum();
+            if shown_width <= width {
+                break;
+            }
+            self.first_visible = self.first_visible.saturating_add(1);
+        }
+    }
+}
+
+impl UIComponent for TabBar {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        let labels: Vec<String> = self.tabs.iter().map(Tab::label).collect();
+        // A column at each end is kept for the marks showing there are more tabs
+        let width = self.size.width.saturating_sub(2);
+        self.scroll_to_current(&labels, width);
+
+        let mut text = String::from(if self.first_visible > 0 { "<" } else { " " });
+        let mut current_span = 0..0;
+        let mut shown_width: usize = 0;
+        let mut has_more = false;
+        self.shown.clear();
+
+        for (idx, label) in labels.iter().enumerate().skip(self.first_visible) {
+            let label_width = label.width();
+            // The current tab is shown even if it is too wide, cut off at the end of the row
+            if shown_width.saturating_add(label_width) > width && idx != self.current {
+                has_more = true;
+                break;
+            }
+            if idx == self.current {
+                current_span = text.len()..text.len().saturating_add(label.len());
+            }
+            // After the column of the mark for hidden tabs
+            let start = shown_width.saturating_add(1);
+            self.shown
+                .push((start..start.saturating_add(label_width), idx));
+            text.push_str(label);
+            shown_width = shown_width.saturating_add(label_width);
+        }
+
+        if has_more {
+            text.push_str(&" ".repeat(width.saturating_sub(shown_width)));
+            text.push('>');
+        }
+
+        Terminal::print_inverted_row_except(origin.row, &text, current_span)
+    }
+
+    fn set_size(&mut self, to: Size) {
+        self.size = to;
+    }
+
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&mut self) -> bool {
+        self.needs_redraw
+    }
+}
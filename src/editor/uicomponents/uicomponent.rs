@@ -1,4 +1,4 @@
-use crate::{editor::Size, prelude::RowIdx};
+use crate::{editor::Size, prelude::Position};
 use std::io::Error;
 
 pub trait UIComponent {
@@ -9,7 +9,7 @@ pub trait UIComponent {
     }
 
     /// Draw this component if it's visible and in need of re-drawing
-    fn render(&mut self, origin: RowIdx) {
+    fn render(&mut self, origin: Position) {
         if self.needs_redraw() {
             if let Err(err) = self.draw(origin) {
                 #[cfg(debug_assertions)]
@@ -27,7 +27,7 @@ pub trait UIComponent {
     }
 
     /// Method to actually draw the component, must be implemented by each component
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error>;
+    fn draw(&mut self, origin: Position) -> Result<(), Error>;
 
     /// Updates the size. Needs to be implemented by each component.
     fn set_size(&mut self, to: Size);
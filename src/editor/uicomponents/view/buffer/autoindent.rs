@@ -0,0 +1,50 @@
+/// Brackets which indent the lines between them, as pairs of opening and closing bracket
+const BRACKETS: [(char, char); 3] = [('{', '}'), ('(', ')'), ('[', ']')];
+
+pub fn leading_whitespace(line: &str) -> &str {
+    let end = line.len().saturating_sub(line.trim_start().len());
+    line.get(..end).unwrap_or_default()
+}
+
+/// Returns the closing bracket for the opening bracket a text ends with, if it does
+pub fn closer_after(text: &str) -> Option<char> {
+    let last = text.trim_end().chars().last()?;
+    BRACKETS
+        .iter()
+        .find(|(opener, _)| *opener == last)
+        .map(|(_, closer)| *closer)
+}
+
+pub fn opener_of(closer: char) -> Option<char> {
+    BRACKETS
+        .iter()
+        .find(|(_, other)| *other == closer)
+        .map(|(opener, _)| *opener)
+}
+
+/// Finds the opening bracket matching a closing one, looking backwards through the
+/// given lines, nearest first, and no further than it. Returns the indentation of the
+/// line it is on. Brackets in strings and comments are counted as well.
+pub fn indent_of_opener<S: AsRef<str>>(
+    lines_before: impl Iterator<Item = S>,
+    closer: char,
+) -> Option<String> {
+    let opener = opener_of(closer)?;
+    let mut depth: usize = 0;
+
+    for line in lines_before {
+        let line = line.as_ref();
+        for ch in line.chars().rev() {
+            if ch == closer {
+                depth = depth.saturating_add(1);
+            } else if ch == opener {
+                if depth == 0 {
+                    return Some(leading_whitespace(line).to_string());
+                }
+                depth = depth.saturating_sub(1);
+            }
+        }
+    }
+
+    None
+}
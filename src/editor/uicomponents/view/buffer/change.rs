@@ -0,0 +1,74 @@
+use crate::prelude::ByteIdx;
+
+/// A single reversible modification of the buffer text.
+/// Offsets are byte offsets into the buffer text, where lines are joined by `\n`.
+#[derive(Clone, Debug)]
+pub enum Change {
+    Insert { at: ByteIdx, text: String },
+    Remove { at: ByteIdx, text: String },
+}
+
+impl Change {
+    /// Returns the change that reverts this one
+    pub fn inverted(&self) -> Self {
+        match self {
+            Self::Insert { at, text } => Self::Remove {
+                at: *at,
+                text: text.clone(),
+            },
+            Self::Remove { at, text } => Self::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+        }
+    }
+
+    /// Where the caret belongs once this change has been applied
+    pub fn caret_offset(&self) -> ByteIdx {
+        match self {
+            Self::Insert { at, text } => at.saturating_add(text.len()),
+            Self::Remove { at, .. } => *at,
+        }
+    }
+
+    /// Tries to fold `next` into this change, so that consecutive typing or deleting
+    /// becomes a single undo step. Returns whether `next` was merged.
+    pub fn coalesce(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (
+                Self::Insert { at, text },
+                Self::Insert {
+                    at: next_at,
+                    text: next_text,
+                },
+            ) if !text.contains('\n')
+                && !next_text.contains('\n')
+                && at.saturating_add(text.len()) == *next_at =>
+            {
+                text.push_str(next_text);
+                true
+            }
+            (
+                Self::Remove { at, text },
+                Self::Remove {
+                    at: next_at,
+                    text: next_text,
+                },
+            ) if !text.contains('\n') && !next_text.contains('\n') => {
+                if next_at.saturating_add(next_text.len()) == *at {
+                    // Deleting backwards
+                    text.insert_str(0, next_text);
+                    *at = *next_at;
+                    true
+                } else if next_at == at {
+                    // Deleting forwards
+                    text.push_str(next_text);
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use super::change::Change;
+use super::transaction::Transaction;
+
+/// Reversible log of all changes made to a buffer.
+/// Each transaction has a unique id, which allows telling whether the buffer
+/// is back at the state it was last saved in.
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    next_id: usize,
+    saved_id: usize,
+    open_groups: usize,
+    sealed: bool,
+}
+
+impl History {
+    /// Records a change which has just been applied to the buffer
+    pub fn record(&mut self, change: Change) {
+        self.redo_stack.clear();
+
+        let Some(transaction) = self.undo_stack.last_mut().filter(|_| !self.sealed) else {
+            self.push_transaction(change);
+            return;
+        };
+
+        if self.open_groups > 0 {
+            transaction.changes.push(change);
+        } else if !transaction
+            .changes
+            .last_mut()
+            .is_some_and(|last| last.coalesce(&change))
+        {
+            self.push_transaction(change);
+        }
+    }
+
+    /// Starts a group: every change recorded until the matching `end_group` becomes one undo step
+    pub fn begin_group(&mut self) {
+        if self.open_groups == 0 {
+            self.sealed = true;
+        }
+        self.open_groups = self.open_groups.saturating_add(1);
+    }
+
+    pub fn end_group(&mut self) {
+        debug_assert!(self.open_groups > 0, "Ending a group which was never begun");
+        self.open_groups = self.open_groups.saturating_sub(1);
+        if self.open_groups == 0 {
+            self.sealed = true;
+        }
+    }
+
+    /// Prevents the next change from being merged into the current undo step
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Moves the latest transaction onto the redo stack and returns it
+    pub fn undo(&mut self) -> Option<Transaction> {
+        let transaction = self.undo_stack.pop()?;
+        self.redo_stack.push(transaction.clone());
+        self.sealed = true;
+        Some(transaction)
+    }
+
+    /// Moves the latest undone transaction back onto the undo stack and returns it
+    pub fn redo(&mut self) -> Option<Transaction> {
+        let transaction = self.redo_stack.pop()?;
+        self.undo_stack.push(transaction.clone());
+        self.sealed = true;
+        Some(transaction)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_id = self.current_id();
+        self.sealed = true;
+    }
+
+    pub fn is_at_saved_state(&self) -> bool {
+        self.current_id() == self.saved_id
+    }
+
+    fn current_id(&self) -> usize {
+        self.undo_stack
+            .last()
+            .map_or(0, |transaction| transaction.id)
+    }
+
+    fn push_transaction(&mut self, change: Change) {
+        // Ids start at 1, 0 is the state before any transaction
+        self.next_id = self.next_id.saturating_add(1);
+        self.undo_stack.push(Transaction {
+            id: self.next_id,
+            changes: vec![change],
+        });
+        self.sealed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, History};
+
+    fn insert(at: usize, text: &str) -> Change {
+        Change::Insert {
+            at,
+            text: text.to_string(),
+        }
+    }
+
+    fn remove(at: usize, text: &str) -> Change {
+        Change::Remove {
+            at,
+            text: text.to_string(),
+        }
+    }
+
+    /// Texts of the changes in each undo step, latest first
+    fn undo_all(history: &mut History) -> Vec<Vec<String>> {
+        std::iter::from_fn(|| history.undo())
+            .map(|transaction| {
+                transaction
+                    .changes
+                    .iter()
+                    .map(|change| match change {
+                        Change::Insert { text, .. } => format!("+{text}"),
+                        Change::Remove { text, .. } => format!("-{text}"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn coalesces_typing_and_deleting() {
+        let mut history = History::default();
+        history.record(insert(0, "a"));
+        history.record(insert(1, "b"));
+        // Not adjacent, so a new step
+        history.record(insert(0, "c"));
+        // Backspace, then delete
+        history.record(remove(3, "b"));
+        history.record(remove(2, "a"));
+        history.record(remove(2, "x"));
+        // Line breaks end a step
+        history.record(insert(2, "\n"));
+        history.record(insert(3, "d"));
+
+        assert_eq!(
+            undo_all(&mut history),
+            vec![
+                vec!["+d"],
+                vec!["+\n"],
+                vec!["-abx"],
+                vec!["+c"],
+                vec!["+ab"]
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_and_seals_steps() {
+        let mut history = History::default();
+        history.record(insert(0, "a"));
+        history.begin_group();
+        history.record(insert(1, "b"));
+        history.begin_group();
+        history.record(insert(5, "\n"));
+        history.end_group();
+        history.record(remove(0, "a"));
+        history.end_group();
+        history.record(insert(1, "c"));
+        history.seal();
+        history.record(insert(2, "d"));
+
+        assert_eq!(
+            undo_all(&mut history),
+            vec![vec!["+d"], vec!["+c"], vec!["+b", "+\n", "-a"], vec!["+a"]]
+        );
+    }
+
+    #[test]
+    fn tells_whether_at_the_saved_state() {
+        let mut history = History::default();
+        assert!(history.is_at_saved_state());
+
+        history.record(insert(0, "a"));
+        assert!(!history.is_at_saved_state());
+        history.mark_saved();
+        assert!(history.is_at_saved_state());
+
+        // Typing after saving doesn't go into the saved step
+        history.record(insert(1, "b"));
+        assert!(!history.is_at_saved_state());
+        history.undo();
+        assert!(history.is_at_saved_state());
+        history.undo();
+        assert!(!history.is_at_saved_state());
+        history.redo();
+        assert!(history.is_at_saved_state());
+
+        // A new step in place of an undone one is not the saved state, though as many steps
+        // are on the undo stack
+        history.undo();
+        history.record(insert(0, "c"));
+        assert!(!history.is_at_saved_state());
+    }
+}
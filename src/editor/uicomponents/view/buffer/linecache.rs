@@ -0,0 +1,98 @@
+use super::Line;
+use crate::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+/// Most lines kept at once. Once there are more, the least recently used one is dropped,
+/// so that walking through a huge file doesn't keep all of it built.
+const MAX_LINES: usize = 4096;
+
+/// `Line`s built from the rope, kept until their text changes, so that drawing,
+/// moving the caret and wrapping don't rebuild the same lines over and over
+#[derive(Default)]
+pub struct LineCache {
+    /// Cached lines, with when they were last used
+    lines: HashMap<LineIdx, (Rc<Line>, u64)>,
+    /// Indices of the cached lines by when they were last used, least recently used first
+    by_use: BTreeMap<u64, LineIdx>,
+    /// Counts the uses of lines, to tell which was used last
+    uses: u64,
+}
+
+impl LineCache {
+    /// Returns the cached line at an index, building it if it isn't cached
+    pub fn get_or_build(&mut self, idx: LineIdx, build: impl FnOnce() -> Line) -> Rc<Line> {
+        self.uses = self.uses.saturating_add(1);
+
+        if let Some((line, last_used)) = self.lines.get_mut(&idx) {
+            self.by_use.remove(last_used);
+            *last_used = self.uses;
+            self.by_use.insert(self.uses, idx);
+            return Rc::clone(line);
+        }
+
+        if self.lines.len() >= MAX_LINES
+            && let Some((_, least_recently_used)) = self.by_use.pop_first()
+        {
+            self.lines.remove(&least_recently_used);
+        }
+        let line = Rc::new(build());
+        self.lines.insert(idx, (Rc::clone(&line), self.uses));
+        self.by_use.insert(self.uses, idx);
+        line
+    }
+
+    /// Forgets a line whose text changed, without the line breaks changing
+    pub fn invalidate(&mut self, idx: LineIdx) {
+        if let Some((_, last_used)) = self.lines.remove(&idx) {
+            self.by_use.remove(&last_used);
+        }
+    }
+
+    /// Forgets a line which `count` lines were broken off, moving the lines after it down
+    pub fn lines_inserted(&mut self, idx: LineIdx, count: usize) {
+        self.invalidate(idx);
+        if count > 0 {
+            self.move_lines_after(idx, |line_idx| line_idx.saturating_add(count));
+        }
+    }
+
+    /// Forgets a line which the `count` lines after it were joined into, and those lines,
+    /// moving the lines after them up
+    pub fn lines_removed(&mut self, idx: LineIdx, count: usize) {
+        if count == 0 {
+            self.invalidate(idx);
+            return;
+        }
+
+        let joined = idx..=idx.saturating_add(count);
+        for (_, (_, last_used)) in self
+            .lines
+            .extract_if(|line_idx, _| joined.contains(line_idx))
+        {
+            self.by_use.remove(&last_used);
+        }
+        self.move_lines_after(*joined.end(), |line_idx| line_idx.saturating_sub(count));
+    }
+
+    /// Gives the lines after an index new indices
+    fn move_lines_after(&mut self, idx: LineIdx, to: impl Fn(LineIdx) -> LineIdx) {
+        let moved: Vec<_> = self
+            .lines
+            .extract_if(|line_idx, _| *line_idx > idx)
+            .collect();
+        for (line_idx, (line, last_used)) in moved {
+            let line_idx = to(line_idx);
+            self.by_use.insert(last_used, line_idx);
+            self.lines.insert(line_idx, (line, last_used));
+        }
+    }
+
+    /// Forgets all lines, for when all of them changed
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.by_use.clear();
+    }
+}
@@ -0,0 +1,1001 @@
+use super::highlighter::{Highlighter, SyntaxState};
+use super::{Line, Location};
+use crate::editor::annotatedstring::AnnotatedString;
+use crate::editor::atomicwrite::write_atomically;
+use crate::editor::editorconfig::EditorConfig;
+use crate::editor::fileformat::{Encoding, FileFormat, LineEnding};
+use crate::editor::fileinfo::FileInfo;
+use crate::editor::filestamp::{FileStamp, HashingReader, HashingWriter};
+use crate::editor::filetype::FileType;
+use crate::editor::indentstyle::IndentStyle;
+use crate::editor::searchquery::SearchQuery;
+use crate::prelude::*;
+use change::Change;
+use history::History;
+use linecache::LineCache;
+use ropey::Rope;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use swapfile::{SwapFile, describe_difference};
+mod autoindent;
+mod change;
+mod history;
+mod linecache;
+mod reflow;
+mod swapfile;
+mod transaction;
+
+/// Lines between those whose syntax highlighting state is kept, so that highlighting the lines
+/// on screen only needs to go through the lines after the last kept state before them
+const SYNTAX_CHECKPOINT_INTERVAL: LineIdx = 256;
+
+/// The text being edited. It is kept in a rope, with lines joined by `\n`;
+/// `Line`s, with their graphemes and widths, are only built when a line is needed.
+#[derive(Default)]
+pub struct Buffer {
+    text: Rope,
+    file_info: FileInfo,
+    history: History,
+    /// Format of the file when it was last loaded or saved
+    saved_format: FileFormat,
+    /// Lines which end with CRLF, in order, if the file mixes line endings
+    crlf_lines: Vec<LineIdx>,
+    /// Counts every change to the text, including undos and redos
+    revision: usize,
+    swap_file: Option<SwapFile>,
+    /// Whether another program was found to have changed the file, which is asked about
+    /// once the buffer is shown
+    changed_on_disk: bool,
+    /// Where the caret was when the buffer was last hidden, to return there when it is shown again
+    last_location: Location,
+    indent_style: IndentStyle,
+    /// Settings for the file from `.editorconfig` files
+    editor_config: EditorConfig,
+    line_cache: RefCell<LineCache>,
+    /// Syntax highlighting state at the start of every `SYNTAX_CHECKPOINT_INTERVAL`th line,
+    /// as far as the lines were highlighted since they last changed
+    syntax_checkpoints: RefCell<Vec<SyntaxState>>,
+}
+
+/// Where the text resides
+impl Buffer {
+    /// Checks if a buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.text.len_bytes() == 0
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.history.is_at_saved_state() || self.file_info.get_format() != self.saved_format
+    }
+    /// Whether this is an empty buffer without a file, which was never edited
+    pub fn is_untouched(&self) -> bool {
+        !self.is_file_loaded() && self.is_empty() && !self.is_dirty()
+    }
+
+    pub const fn revision(&self) -> usize {
+        self.revision
+    }
+
+    pub const fn get_last_location(&self) -> Location {
+        self.last_location
+    }
+
+    pub const fn set_last_location(&mut self, location: Location) {
+        self.last_location = location;
+    }
+
+    pub const fn get_indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    pub fn set_indent_style(&mut self, indent_style: IndentStyle) {
+        self.indent_style = indent_style;
+        // Tabs may be as wide as before no more
+        self.line_cache.get_mut().clear();
+    }
+
+    pub const fn get_file_info(&self) -> &FileInfo {
+        &self.file_info
+    }
+
+    /// Returns the line at an index, without its line break. It is built on first use
+    /// and kept until its text changes.
+    fn line(&self, idx: LineIdx) -> Option<Rc<Line>> {
+        (idx < self.height()).then(|| {
+            self.line_cache.borrow_mut().get_or_build(idx, || {
+                Line::with_tab_width(&self.line_text(idx), self.indent_style.tab_width)
+            })
+        })
+    }
+
+    /// Returns the text of the line at an index, without its line break, straight from the
+    /// rope. Cheaper than `line` for a line which is only looked at once.
+    fn line_text(&self, idx: LineIdx) -> Cow<'_, str> {
+        let text: Cow<str> = self.text.line(idx).into();
+        match text {
+            Cow::Borrowed(text) => Cow::Borrowed(text.strip_suffix('\n').unwrap_or(text)),
+            Cow::Owned(mut text) => {
+                if text.ends_with('\n') {
+                    text.pop();
+                }
+                Cow::Owned(text)
+            }
+        }
+    }
+
+    pub fn grapheme_count(&self, idx: LineIdx) -> GraphemeIdx {
+        self.line(idx).map_or(0, |line| line.grapheme_count())
+    }
+
+    pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx) -> GraphemeIdx {
+        self.line(idx).map_or(0, |line| line.width_until(until))
+    }
+
+    pub fn wrap(&self, idx: LineIdx, width: ColIdx) -> Vec<GraphemeIdx> {
+        self.line(idx)
+            .map_or_else(|| vec![0], |line| line.wrap(width))
+    }
+
+    pub fn grapheme_idx_at_col(&self, idx: LineIdx, col: ColIdx) -> GraphemeIdx {
+        self.line(idx)
+            .map_or(0, |line| line.grapheme_idx_at_col(col))
+    }
+
+    pub fn word_start_before(&self, at: Location) -> GraphemeIdx {
+        self.line(at.line_idx)
+            .map_or(0, |line| line.word_start_before(at.grapheme_idx))
+    }
+
+    pub fn get_highlighted_substring(
+        &self,
+        line_idx: LineIdx,
+        range: Range<GraphemeIdx>,
+        highlighter: &Highlighter,
+    ) -> Option<AnnotatedString> {
+        self.line(line_idx).map(|line| {
+            line.get_annotated_visible_substr(range, Some(&highlighter.get_annotations(line_idx)))
+        })
+    }
+
+    /// Highlights the given lines. Syntax highlighting depends on the lines before them,
+    /// and starts at the closest line before them whose state was kept, keeping the states
+    /// of the lines it passes on the way.
+    pub fn highlight_lines(&self, lines: Range<LineIdx>, highlighter: &mut Highlighter) {
+        let mut checkpoints = self.syntax_checkpoints.borrow_mut();
+        let start = if highlighter.has_syntax() {
+            let checkpoint_idx = lines
+                .start
+                .div_euclid(SYNTAX_CHECKPOINT_INTERVAL)
+                .min(checkpoints.len().saturating_sub(1));
+            let start = checkpoint_idx.saturating_mul(SYNTAX_CHECKPOINT_INTERVAL);
+            let state = checkpoints.get(checkpoint_idx).copied().unwrap_or_default();
+            highlighter.resume_syntax_at(start, state);
+            start
+        } else {
+            lines.start
+        };
+
+        for idx in start..lines.end.min(self.height()) {
+            if highlighter.has_syntax()
+                && idx.is_multiple_of(SYNTAX_CHECKPOINT_INTERVAL)
+                && idx.div_euclid(SYNTAX_CHECKPOINT_INTERVAL) == checkpoints.len()
+            {
+                checkpoints.push(highlighter.syntax_state());
+            }
+            if let Some(line) = self.line(idx) {
+                highlighter.highlight(idx, &line);
+            }
+        }
+    }
+
+    /// Loads a file into a buffer. The format it is saved in is the one the file
+    /// has, unless its `.editorconfig` settings say otherwise.
+    pub fn load(filename: &str) -> Result<Self, std::io::Error> {
+        let editor_config = EditorConfig::for_file(Path::new(filename));
+        let (format, text, crlf_lines, disk_stamp) =
+            read_file(Path::new(filename), &editor_config)?;
+        let format = editor_config.file_format(format);
+        let mut file_info = FileInfo::from(filename).with_format(format);
+        file_info.set_disk_stamp(Some(disk_stamp));
+
+        Ok(Self {
+            text,
+            file_info,
+            history: History::default(),
+            saved_format: format,
+            crlf_lines,
+            revision: 0,
+            swap_file: Some(SwapFile::for_file(Path::new(filename))),
+            changed_on_disk: false,
+            last_location: Location::default(),
+            indent_style: IndentStyle::default(),
+            editor_config,
+            line_cache: RefCell::default(),
+            syntax_checkpoints: RefCell::default(),
+        })
+    }
+
+    /// Returns the length of buffer lines
+    pub fn height(&self) -> LineIdx {
+        if self.is_empty() {
+            0
+        } else {
+            self.text.len_lines()
+        }
+    }
+
+    pub fn insert_char(&mut self, char: char, at: Location) {
+        debug_assert!(at.line_idx <= self.height());
+
+        let change = self.insertion_at(&char.to_string(), at);
+        self.apply(change);
+    }
+
+    pub fn delete(&mut self, at: Location) {
+        if let Some(line) = self.line(at.line_idx) {
+            let offset = self.location_to_offset(at);
+
+            let len = if at.grapheme_idx >= line.grapheme_count()
+                && self.height() > at.line_idx.saturating_add(1)
+            {
+                // Joins the next line into this one
+                1
+            } else if at.grapheme_idx < line.grapheme_count() {
+                line.byte_idx_of(at.grapheme_idx.saturating_add(1))
+                    .saturating_sub(line.byte_idx_of(at.grapheme_idx))
+            } else {
+                0
+            };
+
+            if len > 0 {
+                let text = self.text_in(offset..offset.saturating_add(len));
+                self.apply(Change::Remove { at: offset, text });
+            }
+        }
+    }
+
+    /// Breaks the line at a location, indenting the new line like the one it is split from.
+    /// In Rust, a line ending with an opening bracket is followed by one indented a level
+    /// deeper, and a closing bracket right after the caret moves onto a line of its own.
+    /// Returns where the caret goes.
+    pub fn insert_newline(&mut self, at: Location) -> Location {
+        debug_assert!(at.line_idx <= self.height());
+
+        let offset = self.location_to_offset(at);
+        let (before, after) = self
+            .line(at.line_idx)
+            .map(|line| {
+                let (before, after) = line.split_at(line.byte_idx_of(at.grapheme_idx));
+                (before.to_string(), after.to_string())
+            })
+            .unwrap_or_default();
+        let indent = autoindent::leading_whitespace(&before);
+
+        let mut text = format!("\n{indent}");
+        let closer = (self.file_info.get_file_type() == FileType::Rust)
+            .then(|| autoindent::closer_after(&before))
+            .flatten();
+        if closer.is_some() {
+            text.push_str(&self.indent_style.unit());
+        }
+        let caret = offset.saturating_add(text.len());
+        if closer.is_some_and(|closer| after.trim_start().starts_with(closer)) {
+            text.push('\n');
+            text.push_str(indent);
+        }
+
+        self.apply(Change::Insert { at: offset, text });
+        self.offset_to_location(caret)
+    }
+
+    /// Types a closing bracket in Rust on a line with only whitespace before the caret,
+    /// indenting the line like the one with the matching opening bracket, as one undo step.
+    /// Returns where the caret goes, or `None` if the line needs no re-indenting.
+    pub fn insert_indented_closer(&mut self, closer: char, at: Location) -> Option<Location> {
+        if self.file_info.get_file_type() != FileType::Rust {
+            return None;
+        }
+        let line = self.line(at.line_idx)?;
+        let before = line.get(..line.byte_idx_of(at.grapheme_idx))?;
+        if !before.trim().is_empty() {
+            return None;
+        }
+
+        let lines_before = (0..at.line_idx).rev().map(|idx| self.line_text(idx));
+        let indent = autoindent::indent_of_opener(lines_before, closer)?;
+        if indent == before {
+            return None;
+        }
+
+        let start = Location {
+            line_idx: at.line_idx,
+            grapheme_idx: 0,
+        };
+        self.history.begin_group();
+        self.delete_range(start..at);
+        let end = if indent.is_empty() {
+            start
+        } else {
+            self.insert_text(&indent, start)
+        };
+        self.insert_char(closer, end);
+        self.history.end_group();
+
+        Some(Location {
+            grapheme_idx: end.grapheme_idx.saturating_add(1),
+            ..end
+        })
+    }
+
+    /// Returns the indent style the file's `.editorconfig` settings give, falling back
+    /// to the given one adapted to how the text is indented already
+    pub fn detect_indent_style(&self, fallback: IndentStyle) -> IndentStyle {
+        self.editor_config
+            .indent_style(fallback.detected_in(self.text.lines().map(Cow::from)))
+    }
+
+    /// Inserts text which may span several lines as a single undo step,
+    /// returning the location right after the inserted text
+    pub fn insert_text(&mut self, text: &str, at: Location) -> Location {
+        debug_assert!(at.line_idx <= self.height());
+
+        let change = self.insertion_at(text, at);
+        let end = change.caret_offset();
+
+        self.history.begin_group();
+        self.apply(change);
+        self.history.end_group();
+
+        self.offset_to_location(end)
+    }
+
+    /// Returns the text between two locations, with lines joined by `\n`
+    pub fn text_between(&self, range: Range<Location>) -> String {
+        let start = self.location_to_offset(range.start);
+        let end = self.location_to_offset(range.end);
+        self.text_in(start..end)
+    }
+
+    /// Deletes all text between two locations
+    pub fn delete_range(&mut self, range: Range<Location>) {
+        let start = self.location_to_offset(range.start);
+        let end = self.location_to_offset(range.end);
+
+        if start < end {
+            let text = self.text_in(start..end);
+            self.apply(Change::Remove { at: start, text });
+        }
+    }
+
+    /// Returns the lines of the paragraph around a line, or `None` on a blank line
+    pub fn paragraph_around(&self, line_idx: LineIdx) -> Option<Range<LineIdx>> {
+        let text_of = |idx: LineIdx| self.line(idx).map(|line| line.to_string());
+        let current = text_of(line_idx)?;
+        if reflow::is_blank(&current) {
+            return None;
+        }
+
+        let mut start = line_idx;
+        let mut start_text = current.clone();
+        while let Some(previous) = start.checked_sub(1).and_then(text_of)
+            && reflow::continues_paragraph(&previous, &start_text)
+        {
+            start = start.saturating_sub(1);
+            start_text = previous;
+        }
+
+        let mut end = line_idx.saturating_add(1);
+        let mut end_text = current;
+        while let Some(next) = text_of(end)
+            && reflow::continues_paragraph(&end_text, &next)
+        {
+            end = end.saturating_add(1);
+            end_text = next;
+        }
+
+        Some(start..end)
+    }
+
+    /// Re-wraps the given lines to fit in `width` columns, as a single undo step.
+    /// Returns the location at the end of the reflowed text, or `None` if nothing changed.
+    pub fn reflow(&mut self, lines: Range<LineIdx>, width: ColIdx) -> Option<Location> {
+        let old_lines: Vec<String> = lines
+            .clone()
+            .filter_map(|idx| self.line(idx).map(|line| line.to_string()))
+            .collect();
+        let last_idx = lines.end.checked_sub(1)?;
+
+        let new_text = reflow::reflow(&old_lines, width, self.indent_style.tab_width).join("\n");
+        if new_text == old_lines.join("\n") {
+            return None;
+        }
+
+        let start = Location {
+            line_idx: lines.start,
+            grapheme_idx: 0,
+        };
+        let end = Location {
+            line_idx: last_idx,
+            grapheme_idx: self.grapheme_count(last_idx),
+        };
+
+        self.history.begin_group();
+        self.delete_range(start..end);
+        let end = self.insert_text(&new_text, start);
+        self.history.end_group();
+
+        Some(end)
+    }
+
+    /// Reverts the latest transaction, returning where the caret should be placed
+    pub fn undo(&mut self) -> Option<Location> {
+        let transaction = self.history.undo()?;
+        let mut caret = None;
+
+        for change in transaction.changes.iter().rev() {
+            let inverted = change.inverted();
+            self.apply_without_recording(&inverted);
+            caret = Some(inverted.caret_offset());
+        }
+
+        caret.map(|offset| self.offset_to_location(offset))
+    }
+
+    /// Re-applies the latest undone transaction, returning where the caret should be placed
+    pub fn redo(&mut self) -> Option<Location> {
+        let transaction = self.history.redo()?;
+        let mut caret = None;
+
+        for change in &transaction.changes {
+            self.apply_without_recording(change);
+            caret = Some(change.caret_offset());
+        }
+
+        caret.map(|offset| self.offset_to_location(offset))
+    }
+
+    /// Groups all following edits into a single undo step until `end_transaction` is called
+    pub fn begin_transaction(&mut self) {
+        self.history.begin_group();
+    }
+
+    pub fn end_transaction(&mut self) {
+        self.history.end_group();
+    }
+
+    /// Ensures the next edit starts a new undo step instead of extending the current one
+    pub fn seal_history(&mut self) {
+        self.history.seal();
+    }
+
+    /// Builds the change inserting text at a location.
+    /// Typing on the line past the end of the text creates that line first.
+    fn insertion_at(&self, text: &str, at: Location) -> Change {
+        let mut to_insert = String::new();
+        if at.line_idx == self.height() && !self.is_empty() {
+            to_insert.push('\n');
+        }
+        to_insert.push_str(text);
+
+        Change::Insert {
+            at: self.location_to_offset(at),
+            text: to_insert,
+        }
+    }
+
+    fn apply(&mut self, change: Change) {
+        self.apply_without_recording(&change);
+        self.history.record(change);
+    }
+
+    fn apply_without_recording(&mut self, change: &Change) {
+        self.revision = self.revision.wrapping_add(1);
+        let (Change::Insert { at, text } | Change::Remove { at, text }) = change;
+        let line_idx = self.text.byte_to_line((*at).min(self.text.len_bytes()));
+        let line_count = text.matches('\n').count();
+
+        // States at the start of lines up to the changed one don't depend on it
+        self.syntax_checkpoints.get_mut().truncate(
+            line_idx
+                .div_euclid(SYNTAX_CHECKPOINT_INTERVAL)
+                .saturating_add(1),
+        );
+
+        match change {
+            Change::Insert { at, text } => {
+                self.line_cache
+                    .get_mut()
+                    .lines_inserted(line_idx, line_count);
+                self.crlf_lines_inserted(line_idx, line_count);
+                self.insert_at_offset(*at, text);
+            }
+            Change::Remove { at, text } => {
+                self.line_cache
+                    .get_mut()
+                    .lines_removed(line_idx, line_count);
+                self.crlf_lines_removed(line_idx, line_count);
+                self.remove_at_offset(*at..at.saturating_add(text.len()));
+            }
+        }
+    }
+
+    /// Keeps the lines ending with CRLF on their lines when `count` lines are broken off
+    /// a line. The line's ending goes to its last part, and the others end with LF.
+    fn crlf_lines_inserted(&mut self, idx: LineIdx, count: usize) {
+        let first_moved = self.crlf_lines.partition_point(|crlf_idx| *crlf_idx < idx);
+        for crlf_idx in self.crlf_lines.iter_mut().skip(first_moved) {
+            *crlf_idx = crlf_idx.saturating_add(count);
+        }
+    }
+
+    /// Keeps the lines ending with CRLF on their lines when the `count` lines after a line
+    /// are joined into it. It then ends the way the last of them did.
+    fn crlf_lines_removed(&mut self, idx: LineIdx, count: usize) {
+        let first_moved = self.crlf_lines.partition_point(|crlf_idx| *crlf_idx < idx);
+        let first_kept = self
+            .crlf_lines
+            .partition_point(|crlf_idx| *crlf_idx < idx.saturating_add(count));
+        self.crlf_lines.drain(first_moved..first_kept);
+        for crlf_idx in self.crlf_lines.iter_mut().skip(first_moved) {
+            *crlf_idx = crlf_idx.saturating_sub(count);
+        }
+    }
+
+    /// Inserts text which may span several lines at a byte offset
+    fn insert_at_offset(&mut self, at: ByteIdx, text: &str) {
+        let at = self.text.byte_to_char(at);
+        self.text.insert(at, text);
+    }
+
+    /// Removes a byte range which may span several lines
+    fn remove_at_offset(&mut self, range: Range<ByteIdx>) {
+        let start = self.text.byte_to_char(range.start);
+        let end = self.text.byte_to_char(range.end);
+        self.text.remove(start..end);
+    }
+
+    fn text_in(&self, range: Range<ByteIdx>) -> String {
+        let len = self.text.len_bytes();
+        self.text
+            .byte_slice(range.start.min(len)..range.end.min(len))
+            .to_string()
+    }
+
+    /// Converts a location into a byte offset into the buffer text.
+    /// The line past the end maps to the end of the text.
+    pub fn location_to_offset(&self, at: Location) -> ByteIdx {
+        self.line(at.line_idx)
+            .map_or(self.text.len_bytes(), |line| {
+                self.text
+                    .line_to_byte(at.line_idx)
+                    .saturating_add(line.byte_idx_of(at.grapheme_idx))
+            })
+    }
+
+    fn offset_to_location(&self, offset: ByteIdx) -> Location {
+        let (line_idx, byte_idx) = self.offset_to_line_byte(offset);
+
+        Location {
+            line_idx,
+            grapheme_idx: self
+                .line(line_idx)
+                .map_or(0, |line| line.grapheme_idx_of(byte_idx)),
+        }
+    }
+
+    /// Splits a byte offset into the buffer text into a line and a byte offset within it,
+    /// clamping offsets past the end to the end of the last line
+    fn offset_to_line_byte(&self, offset: ByteIdx) -> (LineIdx, ByteIdx) {
+        let offset = offset.min(self.text.len_bytes());
+        let line_idx = self.text.byte_to_line(offset);
+
+        (
+            line_idx,
+            offset.saturating_sub(self.text.line_to_byte(line_idx)),
+        )
+    }
+
+    /// Writes the text to the file, keeping a backup of its previous version if `keep_backup` is set.
+    /// With `trim_trailing_whitespace`, whitespace at the end of lines is left out of the file.
+    /// Returns the stamp of the written file.
+    pub fn save_to_file(
+        &self,
+        file_info: &FileInfo,
+        keep_backup: bool,
+        trim_trailing_whitespace: bool,
+    ) -> Result<Option<FileStamp>, Error> {
+        if let Some(path) = &file_info.get_path() {
+            let format = file_info.get_format();
+            format.check_encodable(self.text.chunks())?;
+
+            let mut hash = 0;
+            write_atomically(path, keep_backup, None, |file| {
+                let mut writer = HashingWriter::new(file);
+                if trim_trailing_whitespace {
+                    let lines = self.text.lines().map(|line| trim_line_end(line.into()));
+                    format.write(&mut writer, lines, &self.crlf_lines)?;
+                } else {
+                    format.write(&mut writer, self.text.chunks(), &self.crlf_lines)?;
+                }
+                hash = writer.hash();
+                Ok(())
+            })?;
+
+            return Ok(Some(FileStamp::new(&fs::metadata(path)?, hash)));
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            panic!("Attempting to save with no file path present");
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            Ok(None)
+        }
+    }
+
+    /// Saves the text to another file, switching to the `.editorconfig` settings for it
+    pub fn save_as(&mut self, file_name: &str, keep_backup: bool) -> Result<(), Error> {
+        let editor_config = EditorConfig::for_file(Path::new(file_name));
+        let trim = editor_config.trim_trailing_whitespace == Some(true);
+        let mut file_info = FileInfo::from(file_name)
+            .with_format(editor_config.file_format(self.file_info.get_format()));
+        let disk_stamp = self.save_to_file(&file_info, keep_backup, trim)?;
+        if trim {
+            self.trim_trailing_whitespace();
+        }
+        file_info.set_disk_stamp(disk_stamp);
+        self.file_info = file_info;
+        self.indent_style = editor_config.indent_style(self.indent_style);
+        self.editor_config = editor_config;
+        self.mark_saved();
+        if let Some(swap_file) = &mut self.swap_file {
+            swap_file.remove();
+        }
+        self.swap_file = Some(SwapFile::for_file(Path::new(file_name)));
+
+        Ok(())
+    }
+
+    pub fn save(&mut self, keep_backup: bool) -> Result<(), Error> {
+        let trim = self.editor_config.trim_trailing_whitespace == Some(true);
+        let disk_stamp = self.save_to_file(&self.file_info, keep_backup, trim)?;
+        if trim {
+            self.trim_trailing_whitespace();
+        }
+        self.file_info.set_disk_stamp(disk_stamp);
+        self.mark_saved();
+
+        Ok(())
+    }
+
+    /// Removes whitespace from the end of every line, as a single undo step.
+    /// Done once the file was saved without it, so that the text matches the file.
+    fn trim_trailing_whitespace(&mut self) {
+        self.history.begin_group();
+        for line_idx in (0..self.height()).rev() {
+            let line = self.line_text(line_idx);
+            let trimmed_len = line.trim_end().len();
+            if trimmed_len < line.len() {
+                let at = self.text.line_to_byte(line_idx).saturating_add(trimmed_len);
+                let text = line.get(trimmed_len..).unwrap_or_default().to_string();
+                self.apply(Change::Remove { at, text });
+            }
+        }
+        self.history.end_group();
+    }
+
+    fn mark_saved(&mut self) {
+        self.history.mark_saved();
+        self.changed_on_disk = false;
+        self.saved_format = self.file_info.get_format();
+        self.remove_swap_file();
+    }
+
+    /// Whether another program changed the file since it was last loaded or saved
+    pub fn is_changed_on_disk(&mut self) -> bool {
+        let (Some(path), Some(disk_stamp)) =
+            (self.file_info.get_path(), self.file_info.get_disk_stamp())
+        else {
+            return false;
+        };
+
+        match disk_stamp.check(path) {
+            Some(disk_stamp) => {
+                self.file_info.set_disk_stamp(Some(disk_stamp));
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Checks whether another program changed the file, marking the buffer if so.
+    /// Returns whether it is marked.
+    pub fn check_changed_on_disk(&mut self) -> bool {
+        if self.is_changed_on_disk() {
+            self.changed_on_disk = true;
+        }
+        self.changed_on_disk
+    }
+
+    /// Returns whether the buffer was marked as changed on disk, removing the mark
+    pub const fn take_changed_on_disk(&mut self) -> bool {
+        std::mem::replace(&mut self.changed_on_disk, false)
+    }
+
+    /// Replaces the text with the file's current contents, as a single undo step
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let Some(path) = self.file_info.get_path().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+
+        let bytes = fs::read(&path)?;
+        let disk_stamp = FileStamp::new(&fs::metadata(&path)?, FileStamp::hash_bytes(&bytes));
+        let (format, text, crlf_lines) = FileFormat::detect(
+            bytes,
+            self.editor_config.encoding,
+            self.editor_config.line_ending,
+        );
+
+        self.replace_text(&text);
+        self.crlf_lines = crlf_lines;
+        *self.file_info.format_mut() = self.editor_config.file_format(format);
+        self.file_info.set_disk_stamp(Some(disk_stamp));
+        self.mark_saved();
+
+        Ok(())
+    }
+
+    /// Replaces the whole text, as a single undo step
+    fn replace_text(&mut self, text: &str) {
+        self.history.begin_group();
+        if !self.is_empty() {
+            let old_text = self.text.to_string();
+            self.apply(Change::Remove {
+                at: 0,
+                text: old_text,
+            });
+        }
+        if !text.is_empty() {
+            self.apply(Change::Insert {
+                at: 0,
+                text: text.to_string(),
+            });
+        }
+        self.history.end_group();
+    }
+
+    // --- Swap File ---
+
+    /// Returns the text of a swap file left behind by an earlier session, if it is newer than the file.
+    /// Only returns it the first time, so recovery is offered once.
+    pub fn take_recoverable_text(&mut self) -> Option<String> {
+        let path = self.file_info.get_path()?;
+        self.swap_file.as_mut()?.read_if_newer_than(path)
+    }
+
+    pub fn describe_recovery(&self, recovered_text: &str) -> String {
+        describe_difference(&self.text.to_string(), recovered_text)
+    }
+
+    /// Replaces the text with recovered text, as a single undo step
+    pub fn recover(&mut self, recovered_text: &str) {
+        self.replace_text(recovered_text);
+
+        if let Some(swap_file) = &mut self.swap_file {
+            swap_file.adopt();
+        }
+    }
+
+    /// Writes the unsaved text to the swap file, or removes the swap file once there is none
+    pub fn update_swap_file(&mut self) -> Result<(), Error> {
+        let is_dirty = self.is_dirty();
+        let (Some(swap_file), Some(path)) = (&mut self.swap_file, self.file_info.get_path()) else {
+            return Ok(());
+        };
+
+        if is_dirty {
+            swap_file.write(self.revision, path, self.text.chunks())
+        } else {
+            self.remove_swap_file();
+            Ok(())
+        }
+    }
+
+    /// Removes the swap file unless it was left behind by an earlier session and not recovered
+    pub fn remove_swap_file(&mut self) {
+        if let Some(swap_file) = self.swap_file.as_mut().filter(|swap| swap.is_owned()) {
+            swap_file.remove();
+        }
+    }
+
+    /// Removes the swap file, even if an earlier session left it behind
+    pub fn discard_swap_file(&mut self) {
+        if let Some(swap_file) = &mut self.swap_file {
+            swap_file.remove();
+        }
+    }
+
+    /// Switches between LF and CRLF line endings for when the file is saved,
+    /// returning the new line ending
+    pub fn toggle_line_ending(&mut self) -> LineEnding {
+        self.crlf_lines.clear();
+        let format = self.file_info.format_mut();
+        format.line_ending = format.line_ending.toggled();
+        format.line_ending
+    }
+
+    /// Finds the match of the query starting exactly at `at`, returning its range
+    /// together with the text it is to be replaced with
+    pub fn replacement_at(
+        &self,
+        query: &SearchQuery,
+        at: Location,
+        replacement: &str,
+    ) -> Option<(Range<Location>, String)> {
+        let line = self.line(at.line_idx)?;
+        let start = line.byte_idx_of(at.grapheme_idx);
+
+        line.find_all(query, start..start.saturating_add(1))
+            .into_iter()
+            .find(|(_, grapheme_idx)| *grapheme_idx == at.grapheme_idx)
+            .map(|(range, _)| {
+                let end = Location {
+                    line_idx: at.line_idx,
+                    grapheme_idx: line.grapheme_idx_of(range.end),
+                };
+                (
+                    at..end,
+                    query.expand_replacement(&line, &range, replacement),
+                )
+            })
+    }
+
+    /// Returns where the matches of the query in the whole buffer start, in order.
+    /// Stops after `limit` of them, returning whether there are more as well.
+    pub fn match_starts(&self, query: &SearchQuery, limit: usize) -> (Vec<Location>, bool) {
+        let mut starts = Vec::new();
+
+        for line_idx in 0..self.height() {
+            if !query.may_match(&self.line_text(line_idx)) {
+                continue;
+            }
+            let Some(line) = self.line(line_idx) else {
+                continue;
+            };
+            for (_, grapheme_idx) in line.find_all(query, 0..line.len()) {
+                if starts.len() == limit {
+                    return (starts, true);
+                }
+                starts.push(Location {
+                    grapheme_idx,
+                    line_idx,
+                });
+            }
+        }
+
+        (starts, false)
+    }
+
+    pub fn search_forward(&self, query: &SearchQuery, from: Location) -> Option<Location> {
+        let mut is_first = true;
+
+        for line_idx in (0..self.height())
+            .cycle()
+            .skip(from.line_idx)
+            .take(self.height().saturating_add(1))
+        //taking one more, to search the current line twice (once from the middle, once from the start)
+        {
+            let from_grapheme_idx = if is_first {
+                is_first = false;
+                from.grapheme_idx
+            } else {
+                0
+            };
+
+            // Only lines which may have a match are built
+            if !query.may_match(&self.line_text(line_idx)) {
+                continue;
+            }
+            if let Some(grapheme_idx) = self
+                .line(line_idx)
+                .and_then(|line| line.search_forward(query, from_grapheme_idx))
+            {
+                return Some(Location {
+                    grapheme_idx,
+                    line_idx,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn search_backward(&self, query: &SearchQuery, from: Location) -> Option<Location> {
+        let mut is_first = true;
+
+        for line_idx in (0..self.height())
+            .rev()
+            .cycle()
+            .skip(
+                self.height()
+                    .saturating_sub(from.line_idx)
+                    .saturating_sub(1),
+            )
+            .take(self.height().saturating_add(1))
+        {
+            let from_grapheme_idx = if is_first {
+                is_first = false;
+                Some(from.grapheme_idx)
+            } else {
+                None
+            };
+
+            if !query.may_match(&self.line_text(line_idx)) {
+                continue;
+            }
+            if let Some(grapheme_idx) = self.line(line_idx).and_then(|line| {
+                line.search_backward(
+                    query,
+                    from_grapheme_idx.unwrap_or_else(|| line.grapheme_count()),
+                )
+            }) {
+                return Some(Location {
+                    grapheme_idx,
+                    line_idx,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub const fn is_file_loaded(&self) -> bool {
+        self.file_info.has_path()
+    }
+}
+
+/// Removes whitespace from the end of a line, keeping its line break
+fn trim_line_end(line: Cow<'_, str>) -> Cow<'_, str> {
+    let content = line.strip_suffix('\n').unwrap_or(&line);
+    let trimmed = content.trim_end();
+    if trimmed.len() == content.len() {
+        return line;
+    }
+
+    let line_break = if line.ends_with('\n') { "\n" } else { "" };
+    Cow::Owned(format!("{trimmed}{line_break}"))
+}
+
+/// Reads a file, returning its format, its text, the lines which end with CRLF if it mixes
+/// line endings, and its stamp. UTF-8 files are read straight into the rope; others are
+/// read whole first, to be decoded as Latin-1.
+fn read_file(
+    path: &Path,
+    editor_config: &EditorConfig,
+) -> Result<(FileFormat, Rope, Vec<LineIdx>, FileStamp), Error> {
+    if editor_config.encoding != Some(Encoding::Latin1) {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let mut reader = HashingReader::new(file);
+        match FileFormat::read_utf8(&mut reader, editor_config.line_ending) {
+            Ok((format, text, crlf_lines)) => {
+                let disk_stamp = FileStamp::new(&metadata, reader.hash());
+                return Ok((format, text, crlf_lines, disk_stamp));
+            }
+            Err(err) if err.kind() == ErrorKind::InvalidData => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let bytes = fs::read(path)?;
+    let disk_stamp = FileStamp::new(&fs::metadata(path)?, FileStamp::hash_bytes(&bytes));
+    let (format, text, crlf_lines) =
+        FileFormat::detect(bytes, editor_config.encoding, editor_config.line_ending);
+    Ok((format, Rope::from_str(&text), crlf_lines, disk_stamp))
+}
@@ -0,0 +1,193 @@
+use super::Line;
+use crate::prelude::*;
+
+/// Comment markers kept at the start of each reflowed line, longest first
+const COMMENT_MARKERS: [&str; 3] = ["///", "//!", "//"];
+
+/// Splits a line into what it starts with, which is kept when reflowing: its indentation,
+/// a comment marker and the whitespace after it. Returns that and the text after it.
+fn split_lead(line: &str) -> (&str, &str) {
+    let indent_len = line.len().saturating_sub(line.trim_start().len());
+    let after_indent = line.get(indent_len..).unwrap_or_default();
+
+    let marker_len = COMMENT_MARKERS
+        .iter()
+        .find(|marker| after_indent.starts_with(*marker))
+        .map_or(0, |marker| marker.len());
+    let after_marker = after_indent.get(marker_len..).unwrap_or_default();
+    let space_len = after_marker
+        .len()
+        .saturating_sub(after_marker.trim_start().len());
+
+    line.split_at(
+        indent_len
+            .saturating_add(marker_len)
+            .saturating_add(space_len),
+    )
+}
+
+fn comment_marker(line: &str) -> &str {
+    split_lead(line).0.trim()
+}
+
+/// Returns the list marker a text starts with, such as `- ` or `1. `, with the whitespace after it
+fn list_marker(text: &str) -> Option<&str> {
+    let after_number = text.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    let marker_end = if after_number.len() < text.len() {
+        after_number
+            .strip_prefix(['.', ')'])
+            .map(|rest| text.len().saturating_sub(rest.len()))
+    } else {
+        text.strip_prefix(['-', '*', '+'])
+            .map(|rest| text.len().saturating_sub(rest.len()))
+    }?;
+
+    let after_marker = text.get(marker_end..).unwrap_or_default();
+    let words = after_marker.trim_start();
+    // A marker needs whitespace after it, and something to mark
+    if words.len() == after_marker.len() || words.is_empty() {
+        return None;
+    }
+    text.get(..text.len().saturating_sub(words.len()))
+}
+
+/// Whether a line has nothing but its comment marker, which separates paragraphs
+pub fn is_blank(line: &str) -> bool {
+    split_lead(line).1.trim().is_empty()
+}
+
+/// Whether a line is part of the same paragraph as the line before it
+pub fn continues_paragraph(previous: &str, line: &str) -> bool {
+    !is_blank(previous)
+        && !is_blank(line)
+        && comment_marker(previous) == comment_marker(line)
+        && list_marker(split_lead(line).1).is_none()
+}
+
+/// Re-wraps lines to fit in `width` columns where the words allow. Each paragraph keeps the
+/// indentation and comment marker of its first line, and the lines of a list item are
+/// indented past its marker. Blank lines are kept as they are.
+pub fn reflow(lines: &[String], width: ColIdx, tab_width: ColIdx) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    for line in lines {
+        if let Some(previous) = paragraph.last()
+            && !continues_paragraph(previous, line)
+        {
+            result.extend(reflow_paragraph(&paragraph, width, tab_width));
+            paragraph.clear();
+        }
+
+        if is_blank(line) {
+            result.push(line.clone());
+        } else {
+            paragraph.push(line);
+        }
+    }
+    result.extend(reflow_paragraph(&paragraph, width, tab_width));
+
+    result
+}
+
+fn reflow_paragraph(lines: &[&str], width: ColIdx, tab_width: ColIdx) -> Vec<String> {
+    let Some(first) = lines.first() else {
+        return Vec::new();
+    };
+
+    let (lead, first_text) = split_lead(first);
+    let marker = list_marker(first_text).unwrap_or_default();
+    let first_prefix = format!("{lead}{marker}");
+    let width_of = |text: &str| Line::with_tab_width(text, tab_width).width();
+    let rest_prefix = format!("{lead}{}", " ".repeat(width_of(marker)));
+    let rest_prefix_width = width_of(&rest_prefix);
+
+    let words = first_text
+        .get(marker.len()..)
+        .unwrap_or_default()
+        .split_whitespace()
+        .chain(
+            lines
+                .iter()
+                .skip(1)
+                .flat_map(|line| split_lead(line).1.split_whitespace()),
+        );
+
+    let mut result = Vec::new();
+    let mut current = first_prefix.clone();
+    let mut current_width = width_of(&first_prefix);
+    let mut is_empty = true;
+
+    for word in words {
+        let word_width = width_of(word);
+
+        if is_empty {
+            is_empty = false;
+        } else if current_width.saturating_add(1).saturating_add(word_width) <= width {
+            current.push(' ');
+            current_width = current_width.saturating_add(1);
+        } else {
+            result.push(std::mem::replace(&mut current, rest_prefix.clone()));
+            current_width = rest_prefix_width;
+        }
+
+        current.push_str(word);
+        current_width = current_width.saturating_add(word_width);
+    }
+    result.push(current);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reflow;
+
+    fn reflowed(lines: &[&str], width: usize) -> Vec<String> {
+        let lines: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        reflow(&lines, width, 4)
+    }
+
+    #[test]
+    fn wraps_and_joins_paragraphs() {
+        assert_eq!(
+            reflowed(&["one two three", "four", "", "five six"], 9),
+            vec!["one two", "three", "four", "", "five six"]
+        );
+        assert_eq!(reflowed(&["a", "b", "c"], 80), vec!["a b c"]);
+    }
+
+    #[test]
+    fn keeps_long_words_whole() {
+        assert_eq!(
+            reflowed(&["short incomprehensibilities x"], 8),
+            vec!["short", "incomprehensibilities", "x"]
+        );
+    }
+
+    #[test]
+    fn keeps_indentation_and_comment_markers() {
+        assert_eq!(
+            reflowed(&["    /// one two three four", "    /// five"], 20),
+            vec!["    /// one two", "    /// three four", "    /// five"]
+        );
+        // A blank comment line separates paragraphs, as does a different marker
+        assert_eq!(
+            reflowed(&["// a", "//", "// b", "/// c"], 80),
+            vec!["// a", "//", "// b", "/// c"]
+        );
+    }
+
+    #[test]
+    fn indents_list_items_past_their_marker() {
+        assert_eq!(
+            reflowed(&["- one two three", "- four", "12. five six"], 10),
+            vec!["- one two", "  three", "- four", "12. five", "    six"]
+        );
+    }
+
+    #[test]
+    fn counts_tabs_by_their_width() {
+        assert_eq!(reflowed(&["\tone two"], 9), vec!["\tone", "\ttwo"]);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::editor::atomicwrite::write_atomically;
+use std::{
+    ffi::OsString,
+    fs,
+    io::{Error, Write},
+    path::{Path, PathBuf},
+};
+
+/// Copy of a buffer's unsaved text, kept next to its file as `.name.hecto-swp`,
+/// from which the changes can be recovered after a crash
+pub struct SwapFile {
+    path: PathBuf,
+    /// Revision of the buffer which was last written, if the swap file was written at all
+    written_revision: Option<usize>,
+    /// Whether this session is in charge of the swap file, as opposed to it being
+    /// left behind by an earlier one which has not been recovered
+    is_owned: bool,
+    /// Whether the swap file was read for recovery already, which is only offered once
+    was_read: bool,
+}
+
+impl SwapFile {
+    pub fn for_file(file_path: &Path) -> Self {
+        let mut file_name = OsString::from(".");
+        file_name.push(file_path.file_name().unwrap_or_default());
+        // Not `.swp`, which is Vim's and would make either editor take the other's for its own
+        file_name.push(".hecto-swp");
+
+        Self {
+            path: file_path.with_file_name(file_name),
+            written_revision: None,
+            is_owned: false,
+            was_read: false,
+        }
+    }
+
+    /// Reads the swap file left behind by an earlier session, if it is newer than the file itself.
+    /// Only reads it the first time, so recovery is offered once.
+    pub fn read_if_newer_than(&mut self, file_path: &Path) -> Option<String> {
+        if std::mem::replace(&mut self.was_read, true) {
+            return None;
+        }
+
+        let swap_modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok()?;
+        let file_modified = fs::metadata(file_path).and_then(|m| m.modified()).ok();
+
+        if file_modified.is_some_and(|file_modified| file_modified > swap_modified) {
+            return None;
+        }
+        fs::read_to_string(&self.path).ok()
+    }
+
+    /// Writes the text unless this revision was written already. The swap file gets the
+    /// permissions of the file it belongs to, since it holds the same contents.
+    /// A swap file this session isn't in charge of is left alone, as it holds the changes
+    /// of an earlier session which may still be recovered.
+    pub fn write<'a>(
+        &mut self,
+        revision: usize,
+        file_path: &Path,
+        mut chunks: impl Iterator<Item = &'a str>,
+    ) -> Result<(), Error> {
+        if self.written_revision == Some(revision) {
+            return Ok(());
+        }
+        if !self.is_owned && fs::symlink_metadata(&self.path).is_ok() {
+            return Ok(());
+        }
+
+        let permissions = fs::metadata(file_path).ok().map(|m| m.permissions());
+        write_atomically(&self.path, false, permissions, |file| {
+            chunks.try_for_each(|chunk| file.write_all(chunk.as_bytes()))
+        })?;
+
+        self.written_revision = Some(revision);
+        self.is_owned = true;
+        Ok(())
+    }
+
+    pub const fn is_owned(&self) -> bool {
+        self.is_owned
+    }
+
+    /// Takes charge of a swap file left behind by an earlier session, once its text was recovered
+    pub const fn adopt(&mut self) {
+        self.is_owned = true;
+    }
+
+    pub fn remove(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        self.written_revision = None;
+        self.is_owned = false;
+    }
+}
+
+/// Longest part of a changed line shown when describing a difference, in chars
+const MAX_EXCERPT_LEN: usize = 30;
+
+/// Describes how the recovered text differs from the file's: where the changed lines start,
+/// how many of them there are on either side, and the first of them
+pub fn describe_difference(file_text: &str, recovered_text: &str) -> String {
+    let file_lines: Vec<&str> = file_text.split('\n').collect();
+    let recovered_lines: Vec<&str> = recovered_text.split('\n').collect();
+
+    let common_start = file_lines
+        .iter()
+        .zip(&recovered_lines)
+        .take_while(|(file_line, recovered_line)| file_line == recovered_line)
+        .count();
+    let common_end = file_lines
+        .iter()
+        .skip(common_start)
+        .rev()
+        .zip(recovered_lines.iter().skip(common_start).rev())
+        .take_while(|(file_line, recovered_line)| file_line == recovered_line)
+        .count();
+
+    let changed_in_file = file_lines
+        .len()
+        .saturating_sub(common_start)
+        .saturating_sub(common_end);
+    let changed_in_recovered = recovered_lines
+        .len()
+        .saturating_sub(common_start)
+        .saturating_sub(common_end);
+
+    if changed_in_file == 0 && changed_in_recovered == 0 {
+        return String::from("No difference.");
+    }
+
+    let describe_side = |lines: &[&str], changed: usize| match lines.get(common_start) {
+        Some(first) if changed > 0 => format!("{changed} line(s) from \"{}\"", excerpt(first)),
+        _ => String::from("none"),
+    };
+    format!(
+        "Line {}: file has {}, swap has {}.",
+        common_start.saturating_add(1),
+        describe_side(&file_lines, changed_in_file),
+        describe_side(&recovered_lines, changed_in_recovered),
+    )
+}
+
+/// Shortens a line to fit into a prompt, marking where it was cut
+fn excerpt(line: &str) -> String {
+    let mut chars = line.trim().chars();
+    let mut result: String = chars.by_ref().take(MAX_EXCERPT_LEN).collect();
+    if chars.next().is_some() {
+        result.push('…');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe_difference;
+
+    #[test]
+    fn shows_the_first_changed_lines() {
+        assert_eq!(
+            describe_difference("a\nb\nc\n", "a\nB\nB2\nc\n"),
+            "Line 2: file has 1 line(s) from \"b\", swap has 2 line(s) from \"B\"."
+        );
+        assert_eq!(
+            describe_difference("a\nc", "a\nb\nc"),
+            "Line 2: file has none, swap has 1 line(s) from \"b\"."
+        );
+        assert_eq!(describe_difference("a\nb", "a\nb"), "No difference.");
+    }
+
+    #[test]
+    fn cuts_long_lines() {
+        let long_line = "x".repeat(40);
+        assert_eq!(
+            describe_difference("", &long_line),
+            format!(
+                "Line 1: file has 1 line(s) from \"\", swap has 1 line(s) from \"{}…\".",
+                "x".repeat(30)
+            )
+        );
+    }
+}
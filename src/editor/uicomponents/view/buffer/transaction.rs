@@ -0,0 +1,8 @@
+use super::change::Change;
+
+/// A group of changes which are undone and redone as a single step
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    pub id: usize,
+    pub changes: Vec<Change>,
+}
@@ -1,12 +1,16 @@
 use crate::{
-    editor::{annotation::Annotation, filetype::FileType, line::Line},
+    editor::{annotation::Annotation, filetype::FileType, line::Line, searchquery::SearchQuery},
     prelude::*,
 };
 use rustsyntaxhighlighter::RustSyntaxHighlighter;
 use searchresulthighlighter::SearchResultHighlighter;
+use selectionhighlighter::SelectionHighlighter;
+use std::ops::Range;
 use syntaxhighlighter::SyntaxHighlighter;
+pub use syntaxhighlighter::SyntaxState;
 mod rustsyntaxhighlighter;
 mod searchresulthighlighter;
+mod selectionhighlighter;
 mod syntaxhighlighter;
 
 fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
@@ -16,45 +20,77 @@ fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighli
     }
 }
 
+// clippy::struct_field_names: each field is a highlighter of a different kind, named after it
 #[derive(Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Highlighter<'a> {
     syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
     search_result_highlighter: Option<SearchResultHighlighter<'a>>,
+    selection_highlighter: Option<SelectionHighlighter>,
 }
 
 impl<'a> Highlighter<'a> {
     pub fn new(
-        matched_word: Option<&'a str>,
+        query: Option<&'a SearchQuery>,
         selected_match: Option<Location>,
+        selection: Option<Range<Location>>,
         file_type: FileType,
     ) -> Self {
-        let search_result_highlighter = matched_word
-            .map(|matched_word| SearchResultHighlighter::new(matched_word, selected_match));
+        let search_result_highlighter =
+            query.map(|query| SearchResultHighlighter::new(query, selected_match));
 
         Self {
             syntax_highlighter: create_syntax_highlighter(file_type),
             search_result_highlighter,
+            selection_highlighter: selection.map(SelectionHighlighter::new),
         }
     }
 
     pub fn get_annotations(&self, idx: LineIdx) -> Vec<Annotation> {
         let mut result = Vec::new();
 
-        if let Some(syntax_highlighter) = &self.syntax_highlighter {
-            if let Some(annotations) = syntax_highlighter.get_annotations(idx) {
-                result.extend_from_slice(annotations);
-            }
+        if let Some(syntax_highlighter) = &self.syntax_highlighter
+            && let Some(annotations) = syntax_highlighter.get_annotations(idx)
+        {
+            result.extend_from_slice(annotations);
         }
 
-        if let Some(search_result_highlighter) = &self.search_result_highlighter {
-            if let Some(annotations) = search_result_highlighter.get_annotations(idx) {
-                result.extend_from_slice(annotations);
-            }
+        if let Some(search_result_highlighter) = &self.search_result_highlighter
+            && let Some(annotations) = search_result_highlighter.get_annotations(idx)
+        {
+            result.extend_from_slice(annotations);
+        }
+
+        // Added last, so the selection is drawn on top of any other highlighting
+        if let Some(selection_highlighter) = &self.selection_highlighter
+            && let Some(annotations) = selection_highlighter.get_annotations(idx)
+        {
+            result.extend_from_slice(annotations);
         }
 
         result
     }
 
+    /// Whether highlighting depends on the lines before the highlighted ones
+    pub const fn has_syntax(&self) -> bool {
+        self.syntax_highlighter.is_some()
+    }
+
+    /// Returns the syntax highlighter's state after the last highlighted line
+    pub fn syntax_state(&self) -> SyntaxState {
+        self.syntax_highlighter
+            .as_ref()
+            .map(|syntax_highlighter| syntax_highlighter.state())
+            .unwrap_or_default()
+    }
+
+    /// Makes syntax highlighting continue at line `idx`, in the state the lines before it left
+    pub fn resume_syntax_at(&mut self, idx: LineIdx, state: SyntaxState) {
+        if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
+            syntax_highlighter.resume_at(idx, state);
+        }
+    }
+
     pub fn highlight(&mut self, idx: LineIdx, line: &Line) {
         if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
             syntax_highlighter.highlight(idx, line);
@@ -63,5 +99,9 @@ impl<'a> Highlighter<'a> {
         if let Some(search_result_highlighter) = &mut self.search_result_highlighter {
             search_result_highlighter.highlight(idx, line);
         }
+
+        if let Some(selection_highlighter) = &mut self.selection_highlighter {
+            selection_highlighter.highlight(idx, line);
+        }
     }
 }
@@ -1,4 +1,4 @@
-use super::syntaxhighlighter::SyntaxHighlighter;
+use super::syntaxhighlighter::{SyntaxHighlighter, SyntaxState};
 use crate::{
     editor::{annotation::Annotation, annotationtype::AnnotationType, line::Line},
     prelude::LineIdx,
@@ -66,7 +66,9 @@ const KNOWN_VALUES: [&str; 4] = ["Some", "None", "Ok", "Err"];
 
 #[derive(Default)]
 pub struct RustSyntaxHighlighter {
+    /// Annotations of the lines from `first_idx` on
     highlights: Vec<Vec<Annotation>>,
+    first_idx: LineIdx,
     ml_comment_balance: usize,
     in_ml_string: bool,
 }
@@ -172,14 +174,14 @@ fn annotate_next_word<F>(
 where
     F: Fn(&str) -> bool,
 {
-    if let Some(word) = string.split_word_bounds().next() {
-        if validator(word) {
-            return Some(Annotation {
-                annotation_type,
-                start: 0,
-                end: word.len(),
-            });
-        }
+    if let Some(word) = string.split_word_bounds().next()
+        && validator(word)
+    {
+        return Some(Annotation {
+            annotation_type,
+            start: 0,
+            end: word.len(),
+        });
     }
 
     None
@@ -216,14 +218,14 @@ fn annotate_known_literal(string: &str) -> Option<Annotation> {
 fn annotate_lifetime_specifier(string: &str) -> Option<Annotation> {
     let mut iter = string.split_word_bound_indices().peekable();
 
-    if let Some((_, "\'")) = iter.next() {
-        if let Some((idx, word)) = iter.next() {
-            return Some(Annotation {
-                annotation_type: AnnotationType::LifetimeSpecifier,
-                start: 0,
-                end: idx.saturating_add(word.len()),
-            });
-        }
+    if let Some((_, "\'")) = iter.next()
+        && let Some((idx, word)) = iter.next()
+    {
+        return Some(Annotation {
+            annotation_type: AnnotationType::LifetimeSpecifier,
+            start: 0,
+            end: idx.saturating_add(word.len()),
+        });
     }
 
     None
@@ -262,10 +264,10 @@ fn is_valid_number(word: &str) -> bool {
 
     let mut chars = word.chars();
 
-    if let Some(first_char) = chars.next() {
-        if !first_char.is_ascii_digit() {
-            return false;
-        }
+    if let Some(first_char) = chars.next()
+        && !first_char.is_ascii_digit()
+    {
+        return false;
     }
 
     let mut seen_dot = false;
@@ -337,7 +339,7 @@ fn is_known_literal(word: &str) -> bool {
 
 impl SyntaxHighlighter for RustSyntaxHighlighter {
     fn highlight(&mut self, idx: LineIdx, line: &Line) {
-        debug_assert_eq!(idx, self.highlights.len());
+        debug_assert_eq!(idx, self.first_idx.saturating_add(self.highlights.len()));
 
         let mut result = Vec::new();
         let mut iter = line.split_word_bound_indices().peekable();
@@ -374,6 +376,20 @@ impl SyntaxHighlighter for RustSyntaxHighlighter {
     }
 
     fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
-        self.highlights.get(idx)
+        self.highlights.get(idx.checked_sub(self.first_idx)?)
+    }
+
+    fn state(&self) -> SyntaxState {
+        SyntaxState {
+            comment_depth: self.ml_comment_balance,
+            in_string: self.in_ml_string,
+        }
+    }
+
+    fn resume_at(&mut self, idx: LineIdx, state: SyntaxState) {
+        self.highlights.clear();
+        self.first_idx = idx;
+        self.ml_comment_balance = state.comment_depth;
+        self.in_ml_string = state.in_string;
     }
 }
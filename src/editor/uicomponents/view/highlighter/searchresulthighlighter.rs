@@ -1,69 +1,50 @@
 use super::syntaxhighlighter::SyntaxHighlighter;
 use crate::{
-    editor::{annotation::Annotation, annotationtype::AnnotationType, line::Line},
+    editor::{
+        annotation::Annotation, annotationtype::AnnotationType, line::Line,
+        searchquery::SearchQuery,
+    },
     prelude::{LineIdx, Location},
 };
 use std::collections::HashMap;
 
-#[derive(Default)]
 pub struct SearchResultHighlighter<'a> {
-    matched_word: &'a str,
+    query: &'a SearchQuery,
     selected_match: Option<Location>,
     highlights: HashMap<LineIdx, Vec<Annotation>>,
 }
 
 impl<'a> SearchResultHighlighter<'a> {
-    pub fn new(matched_word: &'a str, selected_match: Option<Location>) -> Self {
+    pub fn new(query: &'a SearchQuery, selected_match: Option<Location>) -> Self {
         Self {
-            matched_word,
+            query,
             selected_match,
             highlights: HashMap::new(),
         }
     }
 
-    fn highlight_matched_words(&self, line: &Line, result: &mut Vec<Annotation>) {
-        if self.matched_word.is_empty() {
-            return;
-        }
-
-        line.find_all(self.matched_word, 0..line.len())
-            .iter()
-            .for_each(|(start, _)| {
-                result.push(Annotation {
-                    annotation_type: AnnotationType::Match,
-                    start: *start,
-                    end: start.saturating_add(self.matched_word.len()),
-                });
-            });
-    }
-
-    fn highlight_selected_match(&self, result: &mut Vec<Annotation>) {
-        if let Some(selected_match) = self.selected_match {
-            if self.matched_word.is_empty() {
-                return;
-            }
-
-            let start = selected_match.grapheme_idx;
-            result.push(Annotation {
-                annotation_type: AnnotationType::SelectedMatch,
-                start,
-                end: start.saturating_add(self.matched_word.len()),
-            });
-        }
+    fn is_selected_match(&self, idx: LineIdx, grapheme_idx: usize) -> bool {
+        self.selected_match.is_some_and(|selected_match| {
+            selected_match.line_idx == idx && selected_match.grapheme_idx == grapheme_idx
+        })
     }
 }
 
 impl SyntaxHighlighter for SearchResultHighlighter<'_> {
     fn highlight(&mut self, idx: LineIdx, line: &Line) {
-        let mut result = Vec::new();
-
-        self.highlight_matched_words(line, &mut result);
-
-        if let Some(selected_match) = self.selected_match {
-            if selected_match.line_idx == idx {
-                self.highlight_selected_match(&mut result);
-            }
-        }
+        let result = line
+            .find_all(self.query, 0..line.len())
+            .into_iter()
+            .map(|(range, grapheme_idx)| Annotation {
+                annotation_type: if self.is_selected_match(idx, grapheme_idx) {
+                    AnnotationType::SelectedMatch
+                } else {
+                    AnnotationType::Match
+                },
+                start: range.start,
+                end: range.end,
+            })
+            .collect();
 
         self.highlights.insert(idx, result);
     }
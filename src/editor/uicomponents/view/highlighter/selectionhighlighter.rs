@@ -0,0 +1,56 @@
+use super::syntaxhighlighter::SyntaxHighlighter;
+use crate::{
+    editor::{annotation::Annotation, annotationtype::AnnotationType, line::Line},
+    prelude::{LineIdx, Location},
+};
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Default)]
+pub struct SelectionHighlighter {
+    selection: Range<Location>,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl SelectionHighlighter {
+    pub fn new(selection: Range<Location>) -> Self {
+        Self {
+            selection,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for SelectionHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        let Range { start, end } = self.selection;
+
+        if idx < start.line_idx || idx > end.line_idx {
+            return;
+        }
+
+        let start_byte = if idx == start.line_idx {
+            line.byte_idx_of(start.grapheme_idx)
+        } else {
+            0
+        };
+        let end_byte = if idx == end.line_idx {
+            line.byte_idx_of(end.grapheme_idx)
+        } else {
+            line.len()
+        };
+
+        self.highlights.insert(
+            idx,
+            vec![Annotation {
+                annotation_type: AnnotationType::Selection,
+                start: start_byte,
+                end: end_byte,
+            }],
+        );
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
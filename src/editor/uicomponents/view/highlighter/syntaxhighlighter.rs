@@ -3,7 +3,24 @@ use crate::{
     prelude::LineIdx,
 };
 
+/// What a syntax highlighter carries over from a line to the next
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntaxState {
+    /// Depth of the multi-line comments the line ends in
+    pub comment_depth: usize,
+    /// Whether the line ends inside a string
+    pub in_string: bool,
+}
+
 pub trait SyntaxHighlighter {
     fn highlight(&mut self, idx: LineIdx, line: &Line);
     fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>>;
+
+    /// Returns the state after the last highlighted line
+    fn state(&self) -> SyntaxState {
+        SyntaxState::default()
+    }
+
+    /// Makes highlighting continue at line `idx`, in the state the lines before it left
+    fn resume_at(&mut self, _idx: LineIdx, _state: SyntaxState) {}
 }
@@ -1,4 +1,6 @@
 use super::super::{
+    annotatedstring::AnnotatedString,
+    annotationtype::AnnotationType,
     command::{Edit, Move},
     documentstatus::DocumentStatus,
     line::Line,
@@ -6,45 +8,134 @@ use super::super::{
 };
 use super::UIComponent;
 use crate::editor::VERSION;
-use crate::editor::{NAME, filetype::FileType};
+use crate::editor::{
+    NAME, fileformat::LineEnding, linenumbers::LineNumbers, searchoptions::SearchOptions,
+    searchquery::SearchQuery,
+};
 use crate::prelude::*;
-use buffer::Buffer;
+pub use buffer::Buffer;
 use highlighter::Highlighter;
-use log::info;
+use replaceinfo::ReplaceInfo;
 use searchdirection::SearchDirection;
-use searchinfo::SearchInfo;
-use std::{cmp::min, io::Error};
+pub use searchinfo::MatchCount;
+use searchinfo::{MatchList, SearchInfo};
+use std::{cell::RefCell, cmp::Ordering, io::Error, ops::Range, rc::Rc};
+use visualrow::VisualRow;
 mod buffer;
 mod highlighter;
+mod replaceinfo;
 mod searchdirection;
 mod searchinfo;
+mod visualrow;
+
+/// A buffer which several views can show at once
+pub type SharedBuffer = Rc<RefCell<Buffer>>;
+
+/// Most matches counted while searching. Counting stops there, so that searching
+/// huge files for something common doesn't have to go through all of them.
+const MAX_COUNTED_MATCHES: usize = 1000;
 
 #[derive(Default)]
 pub struct View {
-    buffer: Buffer,
+    buffer: SharedBuffer,
+    /// Revision of the buffer when the view was last drawn. Another view showing
+    /// the same buffer may have changed it since, which needs a redraw.
+    drawn_revision: usize,
     needs_redraw: bool,
     size: Size,
     text_location: Location,
+    selection_anchor: Option<Location>,
+    /// Where the text of the last paste went, for pasting an older clipboard entry instead
+    last_paste: Option<Range<Location>>,
     scroll_offset: Position,
     search_info: Option<SearchInfo>,
+    replace_info: Option<ReplaceInfo>,
+    line_numbers: LineNumbers,
+    /// Whether long lines continue on the next rows instead of scrolling horizontally
+    soft_wrap: bool,
+    /// With soft wrap, which of the rows the top line (`scroll_offset.row`) is wrapped into
+    /// is the first one shown
+    scroll_subrow: usize,
 }
 
 impl View {
+    /// Returns a view of the same buffer, with the same caret and scroll position
+    pub fn duplicate(&self) -> Self {
+        Self {
+            buffer: Rc::clone(&self.buffer),
+            needs_redraw: true,
+            size: self.size,
+            text_location: self.text_location,
+            scroll_offset: self.scroll_offset,
+            line_numbers: self.line_numbers,
+            soft_wrap: self.soft_wrap,
+            scroll_subrow: self.scroll_subrow,
+            ..Self::default()
+        }
+    }
+
     // --- Command Handlers ---
 
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
-            Edit::Insert(char) => self.insert_char(char),
-            Edit::InsertNewLine => self.insert_newline(),
-            Edit::DeleteBackward => self.delete_backward(),
-            Edit::Delete => self.delete(),
+            Edit::Insert('\t') => self.replace_selection(Self::insert_tab),
+            Edit::Insert(char) => self.replace_selection(|view| view.insert_char(char)),
+            Edit::InsertNewLine => self.replace_selection(Self::insert_newline),
+            Edit::DeleteBackward => {
+                if !self.delete_selection() {
+                    self.delete_backward();
+                }
+            }
+            Edit::Delete => {
+                if !self.delete_selection() {
+                    self.delete();
+                }
+            }
+            Edit::DeleteWordBackward => {
+                if !self.delete_selection() {
+                    self.delete_word_backward();
+                }
+            }
+            Edit::Undo => {
+                self.clear_selection();
+                self.undo();
+            }
+            Edit::Redo => {
+                self.clear_selection();
+                self.redo();
+            }
+            // The clipboard is owned by the editor, which handles these through
+            // `selected_text`, `cut_selection` and `paste`
+            Edit::Copy | Edit::Cut | Edit::Paste | Edit::PasteOlder => {}
         }
     }
 
     pub fn handle_move_command(&mut self, command: Move) {
+        self.clear_selection();
+        // Moving the caret away ends the current undo step
+        self.buffer.borrow_mut().seal_history();
+        self.move_text_location(command);
+    }
+
+    /// Moves the caret while extending the selection from where it started
+    pub fn handle_select_command(&mut self, command: Move) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.text_location);
+        }
+        self.buffer.borrow_mut().seal_history();
+        self.move_text_location(command);
+        self.set_needs_redraw(true);
+    }
+
+    fn move_text_location(&mut self, command: Move) {
         let Size { height, .. } = self.size;
+        let line_idx = self.text_location.line_idx;
 
         match command {
+            Move::Up if self.soft_wrap => self.move_up_visually(1),
+            Move::Down if self.soft_wrap => self.move_down_visually(1),
+            Move::PageUp if self.soft_wrap => self.move_up_visually(height.saturating_sub(1)),
+            Move::PageDown if self.soft_wrap => self.move_down_visually(height.saturating_sub(1)),
             Move::Up => self.move_up(1),
             Move::Down => self.move_down(1),
             Move::Left => self.move_left(),
@@ -55,54 +146,140 @@ impl View {
             Move::EndOfLine => self.move_to_end_of_line(),
         }
 
+        // The gutter highlights the caret's line, and relative numbers count from it
+        if line_idx != self.text_location.line_idx && self.gutter_width() > 0 {
+            self.set_needs_redraw(true);
+        }
         self.scroll_text_location_into_view();
     }
 
     // --- File Operations ---
 
     pub fn is_file_loaded(&self) -> bool {
-        self.buffer.is_file_loaded()
+        self.buffer.borrow().is_file_loaded()
+    }
+
+    pub fn get_buffer(&self) -> SharedBuffer {
+        Rc::clone(&self.buffer)
+    }
+
+    pub fn shows_buffer(&self, buffer: &SharedBuffer) -> bool {
+        Rc::ptr_eq(&self.buffer, buffer)
+    }
+
+    /// Shows another buffer, with the caret where it was when that buffer was last shown
+    pub fn set_buffer(&mut self, buffer: SharedBuffer) {
+        if self.shows_buffer(&buffer) {
+            return;
+        }
+
+        self.buffer.borrow_mut().seal_history();
+        self.buffer
+            .borrow_mut()
+            .set_last_location(self.text_location);
+        self.text_location = buffer.borrow().get_last_location();
+        self.buffer = buffer;
+        self.selection_anchor = None;
+        self.search_info = None;
+        self.replace_info = None;
+        self.snap_to_buffer();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
     }
 
-    pub fn load(&mut self, filename: &str) -> Result<(), Error> {
-        self.buffer = Buffer::load(filename)?;
+    pub fn save(&mut self, keep_backup: bool) -> Result<(), Error> {
+        self.buffer.borrow_mut().save(keep_backup)?;
         self.set_needs_redraw(true);
 
         Ok(())
     }
 
-    pub fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save()?;
+    pub fn save_as(&mut self, file_name: &str, keep_backup: bool) -> Result<(), Error> {
+        self.buffer.borrow_mut().save_as(file_name, keep_backup)?;
         self.set_needs_redraw(true);
 
         Ok(())
     }
 
-    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)?;
+    pub fn is_changed_on_disk(&mut self) -> bool {
+        self.buffer.borrow_mut().is_changed_on_disk()
+    }
+
+    pub fn take_changed_on_disk(&mut self) -> bool {
+        self.buffer.borrow_mut().take_changed_on_disk()
+    }
+
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.buffer.borrow_mut().reload()?;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
 
         Ok(())
     }
 
+    // --- Swap File ---
+
+    pub fn take_recoverable_text(&mut self) -> Option<String> {
+        self.buffer.borrow_mut().take_recoverable_text()
+    }
+
+    pub fn describe_recovery(&self, recovered_text: &str) -> String {
+        self.buffer.borrow().describe_recovery(recovered_text)
+    }
+
+    pub fn recover(&mut self, recovered_text: &str) {
+        self.buffer.borrow_mut().recover(recovered_text);
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    pub fn discard_swap_file(&mut self) {
+        self.buffer.borrow_mut().discard_swap_file();
+    }
+
     // --- Cursor / Location Management ---
 
     pub fn caret_position(&self) -> Position {
+        if self.soft_wrap {
+            let (row, col) = self.caret_visual_position();
+            return Position {
+                row: self.rows_between(self.top_row(), row, usize::MAX),
+                // Whitespace hanging past the end of a row has the caret at its last column
+                col: col
+                    .min(self.text_width().saturating_sub(1))
+                    .saturating_add(self.gutter_width()),
+            };
+        }
+
         self.text_location_into_position()
             .saturating_sub(self.scroll_offset)
+            .saturating_add(Position {
+                row: 0,
+                col: self.gutter_width(),
+            })
     }
 
     pub fn get_status(&self) -> DocumentStatus {
-        let file_info = self.buffer.get_file_info();
+        let buffer = self.buffer.borrow();
+        let file_info = buffer.get_file_info();
         DocumentStatus {
             filename: format!("{file_info}"),
-            line_count: self.buffer.height(),
+            line_count: buffer.height(),
             text_location: self.text_location,
-            is_modified: self.buffer.is_dirty(),
+            is_modified: buffer.is_dirty(),
             file_type: file_info.get_file_type(),
+            file_format: file_info.get_format(),
         }
     }
 
+    pub fn toggle_line_ending(&mut self) -> LineEnding {
+        self.buffer.borrow_mut().toggle_line_ending()
+    }
+
     fn move_up(&mut self, step: usize) {
         self.text_location.line_idx = self.text_location.line_idx.saturating_sub(step);
         self.snap_to_valid_grapheme();
@@ -154,69 +331,281 @@ impl View {
             .min(self.text_location.grapheme_idx);
     }
 
+    /// Moves the caret and the selection anchor back into the buffer,
+    /// which another view showing it may have shortened
+    fn snap_to_buffer(&mut self) {
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+
+        if let Some(anchor) = self.selection_anchor {
+            let line_idx = anchor.line_idx.min(self.buffer.borrow().height());
+            self.selection_anchor = Some(Location {
+                line_idx,
+                grapheme_idx: anchor.grapheme_idx.min(self.get_line_width(line_idx)),
+            });
+        }
+    }
+
     fn snap_to_valid_line(&mut self) {
-        self.text_location.line_idx = self.text_location.line_idx.min(self.buffer.height());
+        self.text_location.line_idx = self
+            .text_location
+            .line_idx
+            .min(self.buffer.borrow().height());
     }
 
     fn text_location_into_position(&self) -> Position {
         let row = self.text_location.line_idx;
-        debug_assert!(row.saturating_sub(1) <= self.buffer.height());
+        debug_assert!(row.saturating_sub(1) <= self.buffer.borrow().height());
         let col = self
             .buffer
+            .borrow()
             .width_until(row, self.text_location.grapheme_idx);
 
         Position { row, col }
     }
 
     fn get_line_width(&self, at: usize) -> usize {
-        self.buffer.grapheme_count(at)
+        self.buffer.borrow().grapheme_count(at)
     }
 
     fn insert_char(&mut self, char: char) {
+        let indented_closer = matches!(char, '}' | ')' | ']')
+            .then(|| {
+                self.buffer
+                    .borrow_mut()
+                    .insert_indented_closer(char, self.text_location)
+            })
+            .flatten();
+        if let Some(location) = indented_closer {
+            self.text_location = location;
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+            return;
+        }
+
         let old_len = self.get_line_width(self.text_location.line_idx);
-        self.buffer.insert_char(char, self.text_location);
+        self.buffer
+            .borrow_mut()
+            .insert_char(char, self.text_location);
         let new_len = self.get_line_width(self.text_location.line_idx);
 
         let delta = new_len.saturating_sub(old_len);
 
         if delta > 0 {
-            self.handle_move_command(Move::Right);
+            self.move_text_location(Move::Right);
         }
 
         self.set_needs_redraw(true);
     }
 
+    /// Inserts a tab, or spaces up to the next level of indentation if tabs are expanded
+    fn insert_tab(&mut self) {
+        let indent_style = self.buffer.borrow().get_indent_style();
+        if !indent_style.expand_tabs {
+            self.insert_char('\t');
+            return;
+        }
+
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let col = self.buffer.borrow().width_until(line_idx, grapheme_idx);
+        let indent_width = indent_style.indent_width.max(1);
+        let count = indent_width.saturating_sub(col.checked_rem(indent_width).unwrap_or(0));
+
+        for _ in 0..count {
+            self.insert_char(' ');
+        }
+    }
+
     fn delete_backward(&mut self) {
-        self.handle_move_command(Move::Left);
+        self.move_text_location(Move::Left);
 
         if self.text_location.grapheme_idx == 0 && self.text_location.line_idx == 0 {
             return;
         }
 
-        self.buffer.delete(self.text_location);
+        self.buffer.borrow_mut().delete(self.text_location);
+        self.set_needs_redraw(true);
+    }
+
+    /// Deletes from the start of the word before the caret up to the caret,
+    /// or joins the line with the previous one when at its start
+    fn delete_word_backward(&mut self) {
+        let end = self.text_location;
+        if end.grapheme_idx == 0 {
+            self.delete_backward();
+            return;
+        }
+
+        let start = Location {
+            grapheme_idx: self.buffer.borrow().word_start_before(end),
+            ..end
+        };
+        self.buffer.borrow_mut().delete_range(start..end);
+        self.text_location = start;
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
     }
 
     fn delete(&mut self) {
         if self.text_location.grapheme_idx == self.get_line_width(self.text_location.line_idx)
-            && self.text_location.line_idx == self.buffer.height()
+            && self.text_location.line_idx == self.buffer.borrow().height()
         {
             return;
         }
 
-        self.buffer.delete(self.text_location);
+        self.buffer.borrow_mut().delete(self.text_location);
         self.set_needs_redraw(true);
     }
 
     fn insert_newline(&mut self) {
-        self.buffer.insert_newline(self.text_location);
-        self.handle_move_command(Move::Right);
+        let location = self.buffer.borrow_mut().insert_newline(self.text_location);
+        self.text_location = location;
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
     }
 
+    fn undo(&mut self) {
+        let location = self.buffer.borrow_mut().undo();
+        if let Some(location) = location {
+            self.text_location = location;
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    fn redo(&mut self) {
+        let location = self.buffer.borrow_mut().redo();
+        if let Some(location) = location {
+            self.text_location = location;
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// Re-wraps the selected lines, or the paragraph around the caret, to fit in `width` columns.
+    /// Returns whether anything changed.
+    pub fn reflow(&mut self, width: ColIdx) -> bool {
+        let lines = match self.selection() {
+            Some(selection) => {
+                // A selection ending at the start of a line leaves that line out
+                let end = if selection.end.grapheme_idx == 0
+                    && selection.end.line_idx > selection.start.line_idx
+                {
+                    selection.end.line_idx
+                } else {
+                    selection.end.line_idx.saturating_add(1)
+                };
+                Some(selection.start.line_idx..end)
+            }
+            None => self
+                .buffer
+                .borrow()
+                .paragraph_around(self.text_location.line_idx),
+        };
+        let end = lines.and_then(|lines| self.buffer.borrow_mut().reflow(lines, width));
+        let Some(end) = end else {
+            return false;
+        };
+
+        self.selection_anchor = None;
+        self.text_location = end;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+
+        true
+    }
+
+    // --- Selection ---
+
+    pub fn clear_selection(&mut self) {
+        if self.selection_anchor.take().is_some() {
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// Returns the selected range, ordered from start to end, if anything is selected
+    fn selection(&self) -> Option<Range<Location>> {
+        let anchor = self.selection_anchor?;
+
+        match anchor.cmp(&self.text_location) {
+            Ordering::Less => Some(anchor..self.text_location),
+            Ordering::Greater => Some(self.text_location..anchor),
+            Ordering::Equal => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection()
+            .map(|selection| self.buffer.borrow().text_between(selection))
+    }
+
+    /// Removes the selected text and returns it
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let text = self.selected_text()?;
+        self.delete_selection();
+        Some(text)
+    }
+
+    /// Inserts text at the caret as a single undo step, replacing the selection
+    pub fn paste(&mut self, text: &str) {
+        self.replace_selection(|view| {
+            let start = view.text_location;
+            view.text_location = view.buffer.borrow_mut().insert_text(text, start);
+            view.last_paste = Some(start..view.text_location);
+            view.scroll_text_location_into_view();
+            view.set_needs_redraw(true);
+        });
+    }
+
+    /// Replaces the text of the last paste with other text, as a single undo step.
+    /// Must only be called right after pasting, while the text is where it went.
+    pub fn replace_last_paste(&mut self, text: &str) {
+        if let Some(last_paste) = self.last_paste.take() {
+            self.selection_anchor = Some(last_paste.start);
+            self.text_location = last_paste.end;
+        }
+        self.paste(text);
+    }
+
+    /// Deletes the selected text, returning whether anything was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some(selection) = self.selection() else {
+            self.clear_selection();
+            return false;
+        };
+
+        self.buffer.borrow_mut().delete_range(selection.clone());
+        self.text_location = selection.start;
+        self.selection_anchor = None;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+
+        true
+    }
+
+    /// Runs an edit which replaces the selected text, if any, as a single undo step
+    fn replace_selection(&mut self, edit: impl FnOnce(&mut Self)) {
+        if self.selection().is_some() {
+            self.buffer.borrow_mut().begin_transaction();
+            self.delete_selection();
+            edit(self);
+            self.buffer.borrow_mut().end_transaction();
+        } else {
+            self.clear_selection();
+            edit(self);
+        }
+    }
+
     // --- Scrolling ---
 
     fn scroll_text_location_into_view(&mut self) {
+        if self.soft_wrap {
+            self.scroll_wrapped_into_view();
+            return;
+        }
         let Position { row, col } = self.text_location_into_position();
 
         self.scroll_vertically(row);
@@ -242,12 +631,12 @@ impl View {
     }
 
     fn scroll_horizontally(&mut self, to: ColIdx) {
-        let Size { width, .. } = self.size;
+        let width = self.text_width();
 
         let offset_changed = if to < self.scroll_offset.col {
             self.scroll_offset.col = to;
             true
-        } else if to >= self.scroll_offset.row.saturating_add(width) {
+        } else if to >= self.scroll_offset.col.saturating_add(width) {
             self.scroll_offset.col = to.saturating_sub(width).saturating_add(1);
             true
         } else {
@@ -260,21 +649,292 @@ impl View {
     }
 
     fn center_text_location(&mut self) {
-        let Size { height, width } = self.size;
+        if self.soft_wrap {
+            let (row, _) = self.caret_visual_position();
+            self.set_top_row(self.row_above(row, self.size.height.div_ceil(2)));
+            self.set_needs_redraw(true);
+            return;
+        }
         let Position { row, col } = self.text_location_into_position();
-        let vertical_mid = height.div_ceil(2);
-        let horizontal_mid = width.div_ceil(2);
+        let vertical_mid = self.size.height.div_ceil(2);
+        let horizontal_mid = self.text_width().div_ceil(2);
         self.scroll_offset.row = row.saturating_sub(vertical_mid);
         self.scroll_offset.col = col.saturating_sub(horizontal_mid);
         self.set_needs_redraw(true);
     }
 
-    // --- Rendering Helpers ---
+    // --- Soft Wrap ---
+
+    pub fn set_soft_wrap(&mut self, to: bool) {
+        self.soft_wrap = to;
+        self.scroll_offset.col = 0;
+        self.scroll_subrow = 0;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// Returns the index of the grapheme each row of a wrapped line starts at
+    fn row_starts(&self, line_idx: LineIdx) -> Vec<GraphemeIdx> {
+        self.buffer.borrow().wrap(line_idx, self.text_width())
+    }
+
+    fn row_count(&self, line_idx: LineIdx) -> usize {
+        self.row_starts(line_idx).len()
+    }
+
+    /// Returns the row the caret is on and its column in that row
+    fn caret_visual_position(&self) -> (VisualRow, ColIdx) {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let row_starts = self.row_starts(line_idx);
+        let subrow = row_starts
+            .iter()
+            .rposition(|start| *start <= grapheme_idx)
+            .unwrap_or(0);
+        let row_start = row_starts.get(subrow).copied().unwrap_or(0);
+
+        let buffer = self.buffer.borrow();
+        let col = buffer
+            .width_until(line_idx, grapheme_idx)
+            .saturating_sub(buffer.width_until(line_idx, row_start));
+
+        (VisualRow { line_idx, subrow }, col)
+    }
+
+    /// The first row shown, which edits to the top line may have moved past its last row
+    fn top_row(&self) -> VisualRow {
+        let line_idx = self.scroll_offset.row;
+        VisualRow {
+            line_idx,
+            subrow: self
+                .scroll_subrow
+                .min(self.row_count(line_idx).saturating_sub(1)),
+        }
+    }
+
+    const fn set_top_row(&mut self, row: VisualRow) {
+        self.scroll_offset.row = row.line_idx;
+        self.scroll_subrow = row.subrow;
+    }
+
+    /// Returns how many rows `to` is below `from`, counting no further than about `limit`
+    fn rows_between(&self, from: VisualRow, to: VisualRow, limit: usize) -> usize {
+        if to.line_idx == from.line_idx {
+            return to.subrow.saturating_sub(from.subrow);
+        }
+
+        let mut rows = self.row_count(from.line_idx).saturating_sub(from.subrow);
+        for line_idx in from.line_idx.saturating_add(1)..to.line_idx {
+            if rows >= limit {
+                return rows;
+            }
+            rows = rows.saturating_add(self.row_count(line_idx));
+        }
+
+        rows.saturating_add(to.subrow)
+    }
+
+    /// Returns the row `count` rows above `from`, or the first row
+    fn row_above(&self, from: VisualRow, count: usize) -> VisualRow {
+        let mut row = from;
+        let mut count = count;
+
+        while count > row.subrow {
+            let Some(line_idx) = row.line_idx.checked_sub(1) else {
+                return VisualRow::default();
+            };
+            count = count.saturating_sub(row.subrow).saturating_sub(1);
+            row = VisualRow {
+                line_idx,
+                subrow: self.row_count(line_idx).saturating_sub(1),
+            };
+        }
+
+        VisualRow {
+            subrow: row.subrow.saturating_sub(count),
+            ..row
+        }
+    }
+
+    /// Returns the row below `from`, if it isn't the last one
+    fn row_below(&self, from: VisualRow) -> Option<VisualRow> {
+        if from.subrow.saturating_add(1) < self.row_count(from.line_idx) {
+            Some(VisualRow {
+                subrow: from.subrow.saturating_add(1),
+                ..from
+            })
+        } else if from.line_idx < self.buffer.borrow().height() {
+            Some(VisualRow {
+                line_idx: from.line_idx.saturating_add(1),
+                subrow: 0,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn move_up_visually(&mut self, step: usize) {
+        let (row, col) = self.caret_visual_position();
+        self.move_to_visual_row(self.row_above(row, step), col);
+    }
+
+    fn move_down_visually(&mut self, step: usize) {
+        let (mut row, col) = self.caret_visual_position();
+        for _ in 0..step {
+            let Some(below) = self.row_below(row) else {
+                break;
+            };
+            row = below;
+        }
+        self.move_to_visual_row(row, col);
+    }
+
+    /// Moves the caret to the given column of a row, or to the row's end if it is shorter
+    fn move_to_visual_row(&mut self, row: VisualRow, col: ColIdx) {
+        let row_starts = self.row_starts(row.line_idx);
+        let row_start = row_starts.get(row.subrow).copied().unwrap_or(0);
+
+        let buffer = self.buffer.borrow();
+        let start_col = buffer.width_until(row.line_idx, row_start);
+        let mut grapheme_idx =
+            buffer.grapheme_idx_at_col(row.line_idx, start_col.saturating_add(col));
+        drop(buffer);
+
+        // The end of a row which isn't the last is the start of the next one, so
+        // the caret stays on its last grapheme
+        if let Some(next_start) = row_starts.get(row.subrow.saturating_add(1)) {
+            grapheme_idx = grapheme_idx.min(next_start.saturating_sub(1));
+        }
+
+        self.text_location = Location {
+            line_idx: row.line_idx,
+            grapheme_idx,
+        };
+    }
+
+    fn scroll_wrapped_into_view(&mut self) {
+        let height = self.size.height;
+        let (caret_row, _) = self.caret_visual_position();
+        let top = self.top_row();
+
+        let new_top = if caret_row < top {
+            caret_row
+        } else if self.rows_between(top, caret_row, height) >= height {
+            self.row_above(caret_row, height.saturating_sub(1))
+        } else {
+            return;
+        };
+
+        self.set_top_row(new_top);
+        self.set_needs_redraw(true);
+    }
+
+    /// Returns the rows of text to draw, each with the line it shows and that line's columns
+    fn visible_rows(&self) -> Vec<(VisualRow, Range<ColIdx>)> {
+        let Size { height, .. } = self.size;
+        let text_width = self.text_width();
+        let buffer = self.buffer.borrow();
+
+        if !self.soft_wrap {
+            let left = self.scroll_offset.col;
+            let right = left.saturating_add(text_width);
+            return (self.scroll_offset.row..buffer.height())
+                .take(height)
+                .map(|line_idx| {
+                    (
+                        VisualRow {
+                            line_idx,
+                            subrow: 0,
+                        },
+                        left..right,
+                    )
+                })
+                .collect();
+        }
+
+        let mut rows = Vec::new();
+        let top = self.top_row();
+        for line_idx in top.line_idx..buffer.height() {
+            let row_starts = self.row_starts(line_idx);
+            let first_subrow = if line_idx == top.line_idx {
+                top.subrow
+            } else {
+                0
+            };
+
+            for (subrow, row_start) in row_starts.iter().enumerate().skip(first_subrow) {
+                if rows.len() >= height {
+                    return rows;
+                }
+                let start = buffer.width_until(line_idx, *row_start);
+                let end = row_starts.get(subrow.saturating_add(1)).map_or_else(
+                    || buffer.width_until(line_idx, buffer.grapheme_count(line_idx)),
+                    |next_start| buffer.width_until(line_idx, *next_start),
+                );
+                let end = end.min(start.saturating_add(text_width));
+                rows.push((VisualRow { line_idx, subrow }, start..end));
+            }
+        }
+
+        rows
+    }
+
+    // --- Line Numbers ---
+
+    pub fn set_line_numbers(&mut self, to: LineNumbers) {
+        self.line_numbers = to;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// Width of the gutter, wide enough for the number of the last line and a space
+    /// after it. There is none when line numbers are off or the view is too narrow.
+    fn gutter_width(&self) -> usize {
+        if self.line_numbers == LineNumbers::Off {
+            return 0;
+        }
+
+        let line_count = self.buffer.borrow().height().max(1);
+        let digits = line_count
+            .checked_ilog10()
+            .and_then(|log| usize::try_from(log).ok())
+            .unwrap_or_default()
+            .saturating_add(1);
+        let width = digits.saturating_add(1);
+
+        if width < self.size.width { width } else { 0 }
+    }
+
+    /// Width left for the text, right of the gutter
+    fn text_width(&self) -> usize {
+        self.size.width.saturating_sub(self.gutter_width())
+    }
+
+    fn build_gutter(&self, line_idx: LineIdx, width: usize) -> AnnotatedString {
+        let current_line_idx = self.text_location.line_idx;
+        let is_current = line_idx == current_line_idx;
+        let number = if self.line_numbers == LineNumbers::Relative && !is_current {
+            line_idx.abs_diff(current_line_idx)
+        } else {
+            line_idx.saturating_add(1)
+        };
+
+        let digits = width.saturating_sub(1);
+        let mut gutter = AnnotatedString::from(&format!("{number:>digits$} "));
+        let annotation_type = if is_current {
+            AnnotationType::CurrentLineNumber
+        } else {
+            AnnotationType::LineNumber
+        };
+        gutter.add_annotation(annotation_type, 0, digits);
 
-    fn render_line(at: RowIdx, line_text: &str) -> Result<(), Error> {
-        Terminal::print_row(at, line_text)
+        gutter
     }
 
+    // --- Rendering Helpers ---
+
     fn build_welcome_message(width: usize) -> String {
         if width == 0 {
             return String::new();
@@ -294,10 +954,12 @@ impl View {
     // --- Search ---
 
     pub fn enter_search(&mut self) {
+        self.clear_selection();
         self.search_info = Some(SearchInfo {
             prev_location: self.text_location,
             prev_scroll_offset: self.scroll_offset,
             query: None,
+            matches: None,
         });
     }
 
@@ -317,107 +979,285 @@ impl View {
         self.set_needs_redraw(true);
     }
 
-    pub fn search(&mut self, query: &str) {
+    /// Searches for the query from the caret on. On an invalid pattern, the
+    /// previous matches are cleared and the error is returned.
+    pub fn search(&mut self, query: &str, options: SearchOptions) -> Result<(), regex::Error> {
+        let (search_query, result) = match (!query.is_empty())
+            .then(|| SearchQuery::new(query, options))
+            .transpose()
+        {
+            Ok(search_query) => (search_query, Ok(())),
+            Err(err) => (None, Err(err)),
+        };
+
         if let Some(search_info) = self.search_info.as_mut() {
-            search_info.query = Some(Line::from(query));
+            search_info.query = search_query;
+            search_info.matches = None;
         }
         self.search_in_direction(self.text_location, SearchDirection::default());
         self.set_needs_redraw(true);
+
+        result
     }
 
-    /// Panics on debug if query not found
-    fn get_search_query(&self) -> Option<&Line> {
-        let query = self
-            .search_info
+    fn get_search_query(&self) -> Option<&SearchQuery> {
+        self.search_info
             .as_ref()
-            .and_then(|search_info| search_info.query.as_ref());
-
-        debug_assert!(
-            query.is_some(),
-            "Attempting to search with malformed searchinfo present"
-        );
-
-        query
+            .and_then(|search_info| search_info.query.as_ref())
     }
 
-    pub fn search_in_direction(&mut self, from: Location, direction: SearchDirection) {
-        if let Some(location) = self.get_search_query().and_then(|query| {
-            if query.is_empty() {
-                return None;
-            }
-
+    /// Moves the caret to the next match in the given direction.
+    /// Returns whether the search wrapped around the start or end of the buffer.
+    pub fn search_in_direction(&mut self, from: Location, direction: SearchDirection) -> bool {
+        let Some(location) = self.get_search_query().and_then(|query| {
             if direction == SearchDirection::Backward {
-                self.buffer.search_backward(query, from)
+                self.buffer.borrow().search_backward(query, from)
             } else {
-                self.buffer.search_forward(query, from)
+                self.buffer.borrow().search_forward(query, from)
             }
-        }) {
-            self.text_location = location;
-            self.center_text_location();
+        }) else {
+            return false;
+        };
+
+        self.text_location = location;
+        self.center_text_location();
+
+        if direction == SearchDirection::Backward {
+            location >= from
+        } else {
+            location < from
         }
     }
 
-    pub fn search_next(&mut self) {
-        let step_right = self
+    pub fn search_next(&mut self) -> bool {
+        let location = Location {
+            line_idx: self.text_location.line_idx,
+            grapheme_idx: self.text_location.grapheme_idx.saturating_add(1),
+        };
+
+        self.search_in_direction(location, SearchDirection::Forward)
+    }
+
+    pub fn search_prev(&mut self) -> bool {
+        self.search_in_direction(self.text_location, SearchDirection::Backward)
+    }
+
+    /// Returns the position of the match at the caret and the number of matches,
+    /// or `None` when there is no search query. The matches are only found again
+    /// once the query or the buffer changed, and no more than `MAX_COUNTED_MATCHES` of them.
+    pub fn search_match_count(&mut self) -> Option<MatchCount> {
+        let search_info = self.search_info.as_mut()?;
+        let query = search_info.query.as_ref()?;
+        let buffer = self.buffer.borrow();
+
+        let matches = match search_info.matches.take() {
+            Some(matches) if matches.revision == buffer.revision() => matches,
+            _ => {
+                let (starts, is_capped) = buffer.match_starts(query, MAX_COUNTED_MATCHES);
+                MatchList {
+                    revision: buffer.revision(),
+                    starts,
+                    is_capped,
+                }
+            }
+        };
+        let matches = search_info.matches.insert(matches);
+
+        Some(MatchCount {
+            current: matches
+                .starts
+                .binary_search(&self.text_location)
+                .ok()
+                .map(|idx| idx.saturating_add(1)),
+            total: matches.starts.len(),
+            is_capped: matches.is_capped,
+        })
+    }
+
+    // --- Replace ---
+
+    /// Starts replacing matches of the current search query, beginning with the first one
+    /// from the caret on. All replacements form a single undo step, until `finish_replace`.
+    /// Returns whether there is a match to replace.
+    pub fn start_replace(&mut self, replacement: &str) -> bool {
+        self.replace_info = Some(ReplaceInfo {
+            replacement: replacement.to_string(),
+            start: self.buffer.borrow().location_to_offset(self.text_location),
+            wrapped: false,
+            count: 0,
+        });
+        self.buffer.borrow_mut().begin_transaction();
+
+        self.select_replace_match(self.text_location)
+    }
+
+    /// Replaces the match at the caret, returning whether there is another one to replace
+    pub fn replace_match(&mut self) -> bool {
+        let Some((range, replacement)) = self
             .get_search_query()
-            .map_or(1, |query| min(query.grapheme_count(), 1));
+            .zip(self.replace_info.as_ref())
+            .and_then(|(query, replace_info)| {
+                self.buffer.borrow().replacement_at(
+                    query,
+                    self.text_location,
+                    &replace_info.replacement,
+                )
+            })
+        else {
+            return false;
+        };
 
+        let (start_offset, end_offset) = {
+            let buffer = self.buffer.borrow();
+            (
+                buffer.location_to_offset(range.start),
+                buffer.location_to_offset(range.end),
+            )
+        };
+
+        self.buffer.borrow_mut().delete_range(range.clone());
+        let end = if replacement.is_empty() {
+            range.start
+        } else {
+            self.buffer
+                .borrow_mut()
+                .insert_text(&replacement, range.start)
+        };
+
+        if let Some(replace_info) = self.replace_info.as_mut() {
+            replace_info.count = replace_info.count.saturating_add(1);
+            // Keeps the stop point at the same text, which the replacement may have moved
+            if end_offset <= replace_info.start {
+                replace_info.start = replace_info
+                    .start
+                    .saturating_sub(end_offset.saturating_sub(start_offset))
+                    .saturating_add(replacement.len());
+            } else if start_offset < replace_info.start {
+                replace_info.start = start_offset.saturating_add(replacement.len());
+            }
+        }
+        self.set_needs_redraw(true);
+
+        self.select_replace_match(end)
+    }
+
+    /// Leaves the match at the caret as is, returning whether there is another one to replace
+    pub fn skip_match(&mut self) -> bool {
         let location = Location {
             line_idx: self.text_location.line_idx,
-            grapheme_idx: self.text_location.grapheme_idx.saturating_add(step_right),
+            grapheme_idx: self.text_location.grapheme_idx.saturating_add(1),
         };
 
-        self.search_in_direction(location, SearchDirection::Forward);
+        self.select_replace_match(location)
     }
 
-    pub fn search_prev(&mut self) {
-        info!("{:?}", self.text_location);
-        self.search_in_direction(self.text_location, SearchDirection::Backward);
+    pub fn replace_all(&mut self) {
+        while self.replace_match() {}
+    }
+
+    /// Ends the replacement and the search, returning how many matches were replaced
+    pub fn finish_replace(&mut self) -> usize {
+        let count = self.replace_info.take().map_or(0, |replace_info| {
+            self.buffer.borrow_mut().end_transaction();
+            replace_info.count
+        });
+        self.exit_search();
+
+        count
+    }
+
+    /// Moves the caret to the next match from `from` on, unless the matches
+    /// have been walked around the whole buffer back to where the replacement started
+    fn select_replace_match(&mut self, from: Location) -> bool {
+        let Some(location) = self
+            .get_search_query()
+            .and_then(|query| self.buffer.borrow().search_forward(query, from))
+        else {
+            return false;
+        };
+        let offset = self.buffer.borrow().location_to_offset(location);
+        let Some(replace_info) = self.replace_info.as_mut() else {
+            return false;
+        };
+
+        if location < from {
+            if replace_info.wrapped {
+                return false;
+            }
+            replace_info.wrapped = true;
+        }
+
+        if replace_info.wrapped && offset >= replace_info.start {
+            return false;
+        }
+
+        self.text_location = location;
+        self.center_text_location();
+
+        true
     }
 }
 
 impl UIComponent for View {
-    fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        let revision = self.buffer.borrow().revision();
+        if revision != self.drawn_revision {
+            self.snap_to_buffer();
+            // The gutter may have grown with the number of lines
+            self.scroll_text_location_into_view();
+            self.drawn_revision = revision;
+        }
+
         let Size { height, width } = self.size;
+        let gutter_width = self.gutter_width();
+        let text_width = width.saturating_sub(gutter_width);
+        let buffer = self.buffer.borrow();
 
         let top_third = height.div_ceil(3);
         let scroll_top = self.scroll_offset.row;
-        let end_y = origin_row.saturating_add(height);
+        let scroll_bottom = scroll_top.saturating_add(height);
         let query = self
             .search_info
             .as_ref()
-            .and_then(|search_info| search_info.query.as_deref());
+            .and_then(|search_info| search_info.query.as_ref());
         let selected_match = query.is_some().then_some(self.text_location);
-        let mut highlighter = Highlighter::new(
-            query,
-            selected_match,
-            self.buffer.get_file_info().get_file_type(),
-        );
-
-        if self.buffer.get_file_info().get_file_type() != FileType::Text {
-            for current_row in 0..end_y.saturating_add(scroll_top) {
-                self.buffer.highlight(current_row, &mut highlighter);
-            }
-        }
+        let file_type = buffer.get_file_info().get_file_type();
+        let mut highlighter = Highlighter::new(query, selected_match, self.selection(), file_type);
 
-        for current_row in origin_row..end_y {
-            let line_idx = current_row
-                .saturating_sub(origin_row)
-                .saturating_add(scroll_top);
+        buffer.highlight_lines(scroll_top..scroll_bottom, &mut highlighter);
 
-            let left = self.scroll_offset.col;
-            let right = self.scroll_offset.col.saturating_add(width);
+        let visible_rows = self.visible_rows();
 
-            if let Some(annotated_string) =
-                self.buffer
-                    .get_highlighted_substring(line_idx, left..right, &highlighter)
+        for row in 0..height {
+            let position = Position {
+                row: origin.row.saturating_add(row),
+                col: origin.col,
+            };
+
+            if let Some((visual_row, cols)) = visible_rows.get(row)
+                && let Some(annotated_string) = buffer.get_highlighted_substring(
+                    visual_row.line_idx,
+                    cols.clone(),
+                    &highlighter,
+                )
             {
-                Terminal::print_annotated_row(current_row, &annotated_string)?;
-            } else if current_row == top_third && self.buffer.is_empty() {
+                // Rows a line wraps onto leave the gutter blank
+                if gutter_width > 0 && visual_row.subrow == 0 {
+                    let gutter = self.build_gutter(visual_row.line_idx, gutter_width);
+                    Terminal::print_annotated_at(position, &gutter, gutter_width)?;
+                } else if gutter_width > 0 {
+                    Terminal::print_at(position, "", gutter_width)?;
+                }
+                let text_position = Position {
+                    col: position.col.saturating_add(gutter_width),
+                    ..position
+                };
+                Terminal::print_annotated_at(text_position, &annotated_string, text_width)?;
+            } else if row == top_third && buffer.is_empty() && !buffer.is_dirty() {
                 let message = Self::build_welcome_message(width);
-                Self::render_line(current_row, &message)?;
+                Terminal::print_at(position, &message, width)?;
             } else {
-                Self::render_line(current_row, "~")?;
+                Terminal::print_at(position, "~", width)?;
             }
         }
 
@@ -434,6 +1274,6 @@ impl UIComponent for View {
     }
 
     fn needs_redraw(&mut self) -> bool {
-        self.needs_redraw
+        self.needs_redraw || self.buffer.borrow().revision() != self.drawn_revision
     }
 }
@@ -0,0 +1,12 @@
+use crate::prelude::*;
+
+/// State of an ongoing find-and-replace, which walks the matches once from `start`
+pub struct ReplaceInfo {
+    pub replacement: String,
+    /// Byte offset where the walk started. It is moved by replacements before it,
+    /// so that it stays at the same place in the text.
+    pub start: ByteIdx,
+    /// Whether the walk went past the end of the buffer and continued from its start
+    pub wrapped: bool,
+    pub count: usize,
+}
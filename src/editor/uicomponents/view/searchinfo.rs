@@ -1,8 +1,28 @@
-use crate::editor::line::Line;
+use crate::editor::searchquery::SearchQuery;
 use crate::prelude::*;
 
 pub struct SearchInfo {
     pub prev_location: Location,
     pub prev_scroll_offset: Position,
-    pub query: Option<Line>,
+    pub query: Option<SearchQuery>,
+    /// Matches of the query, found when they were first counted
+    pub matches: Option<MatchList>,
+}
+
+/// Where the matches of a query start, kept until the query or the buffer changes
+pub struct MatchList {
+    /// Revision of the buffer the matches were found in
+    pub revision: usize,
+    pub starts: Vec<Location>,
+    /// Whether there are more matches than the ones found, which stopped at a limit
+    pub is_capped: bool,
+}
+
+/// Which match is selected out of how many
+pub struct MatchCount {
+    /// 1-based position of the match at the caret, if it is one of those counted
+    pub current: Option<usize>,
+    pub total: usize,
+    /// Whether there are more matches than `total`
+    pub is_capped: bool,
 }
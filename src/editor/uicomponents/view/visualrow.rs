@@ -0,0 +1,8 @@
+use crate::prelude::*;
+
+/// A row on screen when lines are soft wrapped: a line, and which of the rows it is wrapped into
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VisualRow {
+    pub line_idx: LineIdx,
+    pub subrow: usize,
+}
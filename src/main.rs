@@ -16,5 +16,7 @@ fn main() {
 
     simple_logging::log_to_file("test.log", LevelFilter::Info).unwrap();
 
-    Editor::new(args.get(1)).unwrap().run();
+    Editor::new(args.get(1..).unwrap_or_default())
+        .unwrap()
+        .run();
 }
@@ -1,7 +1,23 @@
 use super::{GraphemeIdx, LineIdx};
+use std::cmp::Ordering;
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Location {
     pub grapheme_idx: GraphemeIdx,
     pub line_idx: LineIdx,
 }
+
+impl Ord for Location {
+    /// Orders locations by their position in the text: first by line, then by grapheme
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.line_idx
+            .cmp(&other.line_idx)
+            .then(self.grapheme_idx.cmp(&other.grapheme_idx))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}